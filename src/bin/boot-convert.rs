@@ -0,0 +1,37 @@
+use advent_of_code::cli::fail;
+use advent_of_code::day_8::{assemble, decode, encode};
+use std::env;
+use std::io::{self, Read, Write};
+
+/// Converts boot code between its text form and bytecode.
+///
+/// Reads from stdin and writes to stdout, e.g. `boot-convert to-bytecode < input.txt > input.bin`.
+/// Text is assembled, so it may contain labels, constants and comments.
+fn main() {
+    let mode = env::args().nth(1).unwrap_or_default();
+    let mut input = Vec::new();
+    io::stdin()
+        .lock()
+        .read_to_end(&mut input)
+        .expect("Expected to read input from stdin");
+
+    let output = match mode.as_str() {
+        "to-bytecode" => {
+            let source = String::from_utf8(input).unwrap_or_else(|e| fail(&e.to_string()));
+            let program = assemble(&source).unwrap_or_else(|e| fail(&e.to_string()));
+            encode(&program)
+        }
+        "to-text" => {
+            let program = decode(&input).unwrap_or_else(|e| fail(&e.to_string()));
+            program
+                .iter()
+                .map(|instruction| format!("{}\n", instruction))
+                .collect::<String>()
+                .into_bytes()
+        }
+        _ => fail("Usage: boot-convert <to-bytecode|to-text>"),
+    };
+    io::stdout()
+        .write_all(&output)
+        .expect("Expected to write output to stdout");
+}
@@ -0,0 +1,65 @@
+use advent_of_code::cli::fail;
+use advent_of_code::day_8::{Command, Debugger, Instruction};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::str::FromStr;
+
+fn main() {
+    // The program is read from the file given on the command line, as the debugger reads its
+    // commands from standard input.
+    let path = env::args()
+        .nth(1)
+        .unwrap_or_else(|| fail("Usage: boot-debug <program>"));
+    let file =
+        File::open(&path).unwrap_or_else(|e| fail(&format!("Could not open '{}': {}", path, e)));
+    let instructions = Instruction::from_reader(&mut BufReader::new(file));
+    println!(
+        "Loaded {} instructions from {}, type 'help' for a list of commands.",
+        instructions.len(),
+        path
+    );
+
+    let mut debugger = Debugger::new(instructions);
+    let stdin = io::stdin();
+    let mut last_command: Option<Command> = None;
+    loop {
+        print!("(boot) ");
+        io::stdout().flush().expect("Expected to flush stdout");
+
+        let mut buffer = String::new();
+        let read = stdin
+            .read_line(&mut buffer)
+            .expect("Expected to read input from stdin");
+        if read == 0 {
+            // End of input.
+            break;
+        }
+
+        // An empty line repeats the previous command.
+        let line = buffer.trim();
+        let command = if line.is_empty() {
+            match &last_command {
+                Some(command) => command.clone(),
+                None => continue,
+            }
+        } else {
+            match Command::from_str(line) {
+                Ok(command) => command,
+                Err(e) => {
+                    println!("{}", e);
+                    continue;
+                }
+            }
+        };
+
+        if command == Command::Quit {
+            break;
+        }
+        match debugger.execute(&command) {
+            Ok(output) => println!("{}", output),
+            Err(e) => println!("{}", e),
+        }
+        last_command = Some(command);
+    }
+}
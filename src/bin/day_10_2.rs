@@ -1,10 +1,17 @@
 use std::io;
+use std::process;
 // Reuse the input reading fn from day_9, as it does not make any sense
 // to reimplement it for this exercise.
-use advent_of_code::day_10::find_adapter_arrangements;
+use advent_of_code::day_10::{analyze_adapters, ChainOptions};
 use advent_of_code::day_9::read_input;
 
 fn main() {
     let adapters = read_input(&mut io::stdin().lock());
-    println!("{}", find_adapter_arrangements(&adapters));
+    match analyze_adapters(&adapters, &ChainOptions::default()) {
+        Ok(chain) => println!("{}", chain.arrangements),
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
 }
@@ -5,9 +5,6 @@ fn main() {
     let mut password_policies: Vec<PasswordPolicy> = read_password_policies();
 
     // Count the number of policies that are valid.
-    password_policies = password_policies
-        .into_iter()
-        .filter(|policy| policy.is_valid_1())
-        .collect();
+    password_policies.retain(|policy| policy.is_valid_1());
     println!("{}", password_policies.len());
 }
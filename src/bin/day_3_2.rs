@@ -7,7 +7,7 @@ fn main() {
     let map: Vec<Vec<char>> = day_3::read_map(&mut io::stdin().lock());
 
     // Define the steps.
-    let steps = vec![(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+    let steps = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
     let mut tree_multiple: i64 = 1;
 
     // Count the number of trees on each of the paths and multiply them.
@@ -5,6 +5,6 @@ fn main() {
     let mut passports = Passport::from_reader(&mut io::stdin().lock());
 
     // Filter invalid passports out.
-    passports = passports.into_iter().filter(|p| p.is_valid_1()).collect();
+    passports.retain(|p| p.is_valid_1());
     println!("{}", passports.len());
 }
@@ -1,9 +1,10 @@
 use std::io;
 
-use advent_of_code::day_5::{parse_boarding_pass, seat_id};
+use advent_of_code::day_5::AircraftLayout;
 
 fn main() {
     let stdin = io::stdin();
+    let layout = AircraftLayout::default();
     let mut max_seat_id = 0;
 
     loop {
@@ -18,7 +19,7 @@ fn main() {
         }
 
         // Parse every boarding pass into a seat id, and retain it if the seat id is higher than the one we had.
-        let seat_id = seat_id(parse_boarding_pass(line, 128, 8));
+        let seat_id = layout.seat_id(layout.decode(line));
         if seat_id > max_seat_id {
             max_seat_id = seat_id;
         }
@@ -1,10 +1,10 @@
 use std::io;
 
-use advent_of_code::day_5::{parse_boarding_pass, seat_id};
+use advent_of_code::day_5::{AircraftLayout, SeatMap};
 
 fn main() {
     let stdin = io::stdin();
-    let mut seat_ids: Vec<i32> = Vec::new();
+    let mut boarding_passes: Vec<String> = Vec::new();
 
     loop {
         let mut buffer = String::new();
@@ -17,25 +17,20 @@ fn main() {
             break;
         }
 
-        // Store seat ids in a vector such that we can find the missing seat.
-        seat_ids.push(seat_id(parse_boarding_pass(line, 128, 8)));
+        boarding_passes.push(line.to_string());
     }
 
-    // Sort the vector of seat ids.
-    seat_ids.sort_unstable();
+    // Build a map of the occupied seats such that we can find the missing seat.
+    let layout = AircraftLayout::default();
+    let seat_map =
+        SeatMap::from_boarding_passes(layout.clone(), boarding_passes.iter().map(|s| s.as_str()));
 
-    // Find the missing seat!
-    for (i, seat_id) in seat_ids.iter().enumerate() {
-        // Our seat was not at the beginning or end...
-        if i == 0 {
-            continue;
-        }
-        if seat_id - seat_ids[i - 1] > 1 {
-            // We've found the missing seat.
-            println!("{}", seat_id - 1);
-            return;
-        }
+    // Our seat is missing, but the seats directly in front of and behind it are occupied.
+    let missing_seats = seat_map.enclosed_missing_seats();
+    if missing_seats.is_empty() {
+        println!("Could not find seat!");
+    }
+    for seat_position in missing_seats {
+        println!("{}", layout.seat_id(seat_position));
     }
-
-    println!("Could not find seat!")
 }
@@ -1,30 +1,50 @@
-use advent_of_code::day_7::{can_contain_bag, BagRule};
-use std::collections::{HashMap, HashSet};
+use advent_of_code::cli::fail;
+use advent_of_code::day_7::{BagGraph, BagRule};
+use std::env;
 use std::io;
 
 fn main() {
-    // Parse rules into a look up table keyed on the color of the bag.
-    let mut rule_table: HashMap<String, BagRule> = HashMap::new();
-    for rule in BagRule::from_reader(&mut io::stdin().lock()).into_iter() {
-        rule_table.insert(rule.bag.color.clone(), rule);
+    // The color of the "target" bag is given on the command line, e.g. `day_7_1 shiny gold`.
+    // Passing `--paths` prints every color that may contain the target bag.
+    let mut print_paths = false;
+    let mut words: Vec<String> = Vec::new();
+    for arg in env::args().skip(1) {
+        if arg == "--paths" {
+            print_paths = true;
+        } else {
+            words.push(arg);
+        }
     }
+    let target_color = if words.is_empty() {
+        "shiny gold".to_string()
+    } else {
+        words.join(" ")
+    };
 
-    // The color of the "target" bag.
-    let target_color = "shiny gold";
+    let rules = BagRule::from_reader(&mut io::stdin().lock());
+    let graph = BagGraph::from_rules(&rules).unwrap_or_else(|e| fail(&e.to_string()));
 
-    // We'll track the colors of the bags that may (eventually) contain target_color in found_colors.
-    let mut found_colors: HashSet<String> = HashSet::new();
+    // Find the colors of the bags that may (eventually) contain target_color.
+    match graph.ancestors(&target_color) {
+        Ok(ancestors) => println!("{}", ancestors.len()),
+        Err(e) => fail(&e.to_string()),
+    }
 
-    // Evaluate all bags that we've parsed from the set of rules, and for every bag test whether
-    // it can contain bags of "target_color".
-    //
-    // We optimize the search by referencing whether a color that we reference is already present
-    // in found_colors.
-    for rule in rule_table.values() {
-        if can_contain_bag(&rule.colors(), target_color, &rule_table, &mut found_colors) {
-            found_colors.insert(rule.bag.color.to_string());
+    // Listing the containment chains requires the rules around target_color to be acyclic.
+    if print_paths {
+        match graph.ancestor_paths(&target_color) {
+            Ok(ancestors) => {
+                for ancestor in ancestors.iter() {
+                    println!(
+                        "{} (depth {}..={}): {}",
+                        ancestor.color,
+                        ancestor.min_depth,
+                        ancestor.max_depth,
+                        ancestor.path.join(" -> ")
+                    );
+                }
+            }
+            Err(e) => fail(&e.to_string()),
         }
     }
-
-    println!("{}", found_colors.len());
 }
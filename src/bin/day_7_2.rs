@@ -1,15 +1,21 @@
-use advent_of_code::day_7::{count_bags, BagRule};
-use std::collections::HashMap;
+use advent_of_code::cli::fail;
+use advent_of_code::day_7::{BagGraph, BagRule};
+use std::env;
 use std::io;
 
 fn main() {
-    // Parse rules into a look up table keyed on the color of the bag.
-    let mut rule_table: HashMap<String, BagRule> = HashMap::new();
-    for rule in BagRule::from_reader(&mut io::stdin().lock()).into_iter() {
-        rule_table.insert(rule.bag.color.clone(), rule);
-    }
+    let rules = BagRule::from_reader(&mut io::stdin().lock());
+    let graph = BagGraph::from_rules(&rules).unwrap_or_else(|e| fail(&e.to_string()));
 
-    // The color of the "target" bag.
-    let target_color = "shiny gold";
-    println!("{}", count_bags(target_color, &rule_table));
+    // The color of the "target" bag is given on the command line, e.g. `day_7_2 shiny gold`.
+    let words: Vec<String> = env::args().skip(1).collect();
+    let target_color = if words.is_empty() {
+        "shiny gold".to_string()
+    } else {
+        words.join(" ")
+    };
+    match graph.count_bags(&target_color) {
+        Ok(count) => println!("{}", count),
+        Err(e) => fail(&e.to_string()),
+    }
 }
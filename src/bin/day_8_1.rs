@@ -1,8 +1,41 @@
-use advent_of_code::day_8::{Instruction, Processor};
+use advent_of_code::day_8::{Instruction, Processor, Profile, TraceMode};
+use std::env;
 use std::io;
 
 fn main() {
+    // Passing `--trace` or `--trace-csv` writes every executed instruction to stderr, and
+    // `--profile` writes a summary of the hottest basic blocks to stderr.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let has_flag = |flag: &str| args.iter().any(|arg| arg == flag);
+    let mode = if has_flag("--trace") || has_flag("--trace-csv") {
+        TraceMode::Full
+    } else if has_flag("--profile") {
+        TraceMode::Profile
+    } else {
+        TraceMode::Off
+    };
+
     let instructions = Instruction::from_reader(&mut io::stdin().lock());
     let mut processor = Processor::new();
-    println!("{}", processor.find_cycle(&instructions).1);
+    processor.set_trace_mode(mode);
+    processor.load(instructions);
+    let outcome = processor.run();
+    println!("{}", processor.accumulator());
+
+    if let Some(trace) = processor.trace() {
+        let mut stderr = io::stderr();
+        if has_flag("--trace") {
+            trace
+                .write_text(&mut stderr)
+                .expect("Failed to write trace");
+        }
+        if has_flag("--trace-csv") {
+            trace.write_csv(&mut stderr).expect("Failed to write trace");
+        }
+        if has_flag("--profile") {
+            Profile::new(processor.program(), trace, &outcome)
+                .write_summary(&mut stderr, 10)
+                .expect("Failed to write profile");
+        }
+    }
 }
@@ -1,7 +1,35 @@
-use advent_of_code::day_8::{find_fix, Instruction};
+use advent_of_code::cli::fail;
+use advent_of_code::day_8::{find_fix, find_repairs, Instruction, RepairOptions};
+use std::env;
 use std::io;
 
 fn main() {
+    // Passing `--max-edits N` lists every minimal repair with at most N edits instead, which
+    // may also flip the signs of arguments (`--flip-signs`) or delete `acc`s (`--delete-acc`).
+    let args: Vec<String> = env::args().skip(1).collect();
+    let max_edits = args.iter().position(|arg| arg == "--max-edits").map(|i| {
+        args.get(i + 1)
+            .and_then(|n| n.parse::<usize>().ok())
+            .unwrap_or_else(|| fail("Usage: day_8_2 [--max-edits N [--flip-signs] [--delete-acc]]"))
+    });
+
     let instructions = Instruction::from_reader(&mut io::stdin().lock());
-    println!("{}", find_fix(&instructions));
+    match max_edits {
+        Some(max_edits) => {
+            let options = RepairOptions {
+                max_edits,
+                flip_signs: args.iter().any(|arg| arg == "--flip-signs"),
+                delete_acc: args.iter().any(|arg| arg == "--delete-acc"),
+                ..RepairOptions::default()
+            };
+            for repair in find_repairs(&instructions, &options) {
+                let edits: Vec<String> = repair.edits.iter().map(|e| e.to_string()).collect();
+                println!("{} ({})", repair.accumulator, edits.join(", "));
+            }
+        }
+        None => match find_fix(&instructions) {
+            Ok(repair) => println!("{}", repair.accumulator),
+            Err(e) => eprintln!("{}", e),
+        },
+    }
 }
@@ -1,7 +1,51 @@
-use advent_of_code::day_9::{find_invalid_number, read_input};
+use advent_of_code::cli::fail;
+use advent_of_code::day_9::{analyze_preamble, read_input, read_numbers, XmasValidator};
+use std::env;
 use std::io;
 
 fn main() {
-    let numbers = read_input(&mut io::stdin().lock());
-    println!("{}", find_invalid_number(25, &numbers).unwrap());
+    // Passing `--all` prints every invalid number with its index, instead of only the first.
+    // The preamble length is 25, unless it is given with `--preamble N`, or `--preamble auto` to
+    // infer it from the data.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let all = args.iter().any(|arg| arg == "--all");
+    let preamble = args.iter().position(|arg| arg == "--preamble").map(|i| {
+        args.get(i + 1)
+            .unwrap_or_else(|| fail("Expected a length after --preamble"))
+    });
+
+    let stdin = io::stdin();
+    let mut invalid: Box<dyn Iterator<Item = _>> = match preamble.map(|p| p.as_str()) {
+        Some("auto") => {
+            let numbers = read_input(&mut stdin.lock());
+            let analysis = analyze_preamble(&numbers, 1..=100);
+            let best = analysis
+                .best()
+                .unwrap_or_else(|| fail("Could not infer the preamble length!"));
+            eprintln!(
+                "Preamble length {} ({:.1}% confidence)",
+                best.len,
+                best.confidence * 100.0
+            );
+            Box::new(XmasValidator::new(best.len).invalid_numbers(numbers))
+        }
+        preamble => {
+            let len = preamble.map_or(25, |p| {
+                p.parse::<usize>()
+                    .unwrap_or_else(|_| fail(&format!("Expected a preamble length, got '{}'", p)))
+            });
+            Box::new(XmasValidator::new(len).invalid_numbers(read_numbers(stdin.lock())))
+        }
+    };
+
+    if all {
+        for number in invalid {
+            println!("{}: {}", number.index, number.value);
+        }
+    } else {
+        let number = invalid
+            .next()
+            .unwrap_or_else(|| fail("Every number is valid!"));
+        println!("{}", number.value);
+    }
 }
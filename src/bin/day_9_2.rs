@@ -1,12 +1,37 @@
-use advent_of_code::day_9::{find_invalid_number, find_sequence, read_input};
+use advent_of_code::cli::fail;
+use advent_of_code::day_9::{analyze_preamble, find_invalid_number, find_sequence, read_input};
+use std::env;
 use std::io;
 
 fn main() {
+    // The preamble length is 25, unless it is given with `--preamble N`, or `--preamble auto` to
+    // infer it from the data.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let preamble = args.iter().position(|arg| arg == "--preamble").map(|i| {
+        args.get(i + 1)
+            .unwrap_or_else(|| fail("Expected a length after --preamble"))
+    });
+
     let numbers = read_input(&mut io::stdin().lock());
-    let invalid_number = find_invalid_number(25, &numbers).unwrap();
+    let preamble_len = match preamble.map(|p| p.as_str()) {
+        Some("auto") => {
+            let analysis = analyze_preamble(&numbers, 1..=100);
+            let best = analysis
+                .best()
+                .unwrap_or_else(|| fail("Could not infer the preamble length!"));
+            best.len as u32
+        }
+        Some(p) => p
+            .parse::<u32>()
+            .unwrap_or_else(|_| fail(&format!("Expected a preamble length, got '{}'", p))),
+        None => 25,
+    };
+
+    let invalid_number = find_invalid_number(preamble_len, &numbers)
+        .unwrap_or_else(|| fail("Every number is valid!"));
     println!(
         "{}",
         find_sequence(invalid_number, &numbers)
-            .unwrap_or_else(|| panic!("Could not find the weakness!"))
+            .unwrap_or_else(|| fail("Could not find the weakness!"))
     );
 }
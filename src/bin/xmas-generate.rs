@@ -0,0 +1,53 @@
+use advent_of_code::cli::fail;
+use advent_of_code::day_9::{generate, GeneratorOptions, PlantedTarget};
+use std::env;
+use std::io;
+
+/// Generates an XMAS stream on stdout, and writes its ground truth to stderr.
+///
+/// Usage: `xmas-generate [--seed N] [--preamble N] [--len N] [--min N] [--max N]
+/// [--invalid-at I,J,...] [--target START..END@POSITION]...`
+fn main() {
+    let mut options = GeneratorOptions::default();
+    let args: Vec<String> = env::args().skip(1).collect();
+    for pair in args.chunks(2) {
+        let (flag, value) = match pair {
+            [flag, value] => (flag.as_str(), value.as_str()),
+            _ => fail(&format!("Expected a value after {}", pair[0])),
+        };
+        let number = |value: &str| {
+            value
+                .parse::<u64>()
+                .unwrap_or_else(|_| fail(&format!("Expected a number, got '{}'", value)))
+        };
+        match flag {
+            "--seed" => options.seed = number(value),
+            "--preamble" => options.preamble_len = number(value) as usize,
+            "--len" => options.len = number(value) as usize,
+            "--min" => options.min_value = number(value),
+            "--max" => options.max_value = number(value),
+            "--invalid-at" => {
+                options.invalid_at = value.split(',').map(|i| number(i) as usize).collect()
+            }
+            "--target" => {
+                let parts: Vec<&str> = value.split(['.', '@']).collect();
+                match parts.as_slice() {
+                    [start, "", end, position] => options.targets.push(PlantedTarget {
+                        range: number(start) as usize..number(end) as usize,
+                        position: number(position) as usize,
+                    }),
+                    _ => fail(&format!("Expected START..END@POSITION, got '{}'", value)),
+                }
+            }
+            _ => fail(&format!("Unknown option '{}'", flag)),
+        }
+    }
+
+    let stream = generate(&options).unwrap_or_else(|e| fail(&e));
+    stream
+        .write_numbers(&mut io::stdout().lock())
+        .expect("Failed to write the numbers");
+    stream
+        .write_truth(&mut io::stderr().lock())
+        .expect("Failed to write the ground truth");
+}
@@ -0,0 +1,8 @@
+use std::process;
+
+/// Prints `message` to stderr and exits with a non-zero status. Binaries use this for errors in
+/// their arguments or input, which should not produce a panic with a backtrace.
+pub fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
@@ -1,153 +1,116 @@
-use std::collections::{HashMap, HashSet};
-
-pub fn test_adapters(adapters: &[u64]) -> i32 {
-    // Sort the list of adapters on rating in ascending order.
-    let mut sorted_adapters = adapters.to_owned();
-    sorted_adapters.sort_unstable();
-
-    // Determine the effective jolt rate of the device adapter.
-    // The rating of the device adapter is 3 jolts higher than the maximum in `adapters`.
-    sorted_adapters.push(sorted_adapters.last().unwrap_or(&0) + 3);
-
-    // Find a chain that uses all adapters.
-    let mut effective_jolt_rating = 0;
-
-    // Keep track of the difference counts in the jolt_differences map.
-    let mut jolt_differences: HashMap<u8, i32> = HashMap::new();
-
-    // Iterate over the sorted list of adapters and keep track of the differences in ratings.
-    for adapter in sorted_adapters.iter() {
-        let difference: u8 = (adapter - effective_jolt_rating) as u8;
-        let entry = jolt_differences.entry(difference).or_insert(0);
-        *entry += 1;
-        effective_jolt_rating = *adapter;
-    }
-
-    jolt_differences[&1] * jolt_differences[&3]
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+/// Configures how adapters may be chained together.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ChainOptions {
+    // The differences in rating that are allowed between adapters that are chained together.
+    pub gaps: BTreeSet<u64>,
+    // The rating of the charging outlet the chain starts at.
+    pub outlet_rating: u64,
+    // How much higher the rating of the device is than the highest rated adapter.
+    pub device_offset: u64,
 }
 
-fn find_permutations_reverse(current_index: usize, adapters: &[u64], cache: &mut [u64]) -> u64 {
-    let mut num_permutations = 0;
-
-    // Check if we've already computed the number of permutations for this sub solution.
-    if current_index > 0 && current_index < adapters.len() && cache[current_index] > 0 {
-        return cache[current_index];
-    }
-
-    // Check if *this* adapter plugs into the charging outlet.
-    let start_adapter = adapters[current_index];
-    if start_adapter <= 3 {
-        // This adapter plugs straight into the charging outlet, which means that we found
-        // an end of the chain.
-        num_permutations += 1;
-    }
-
-    // Check if there are any additional adapters that we can connect to.
-    if current_index < adapters.len() - 1 {
-        // There are more adapters that we could connect to.
-        for next_index in current_index + 1..adapters.len() {
-            if start_adapter - adapters[next_index] > 3 {
-                // The step to the next adapter is too large.
-                break;
-            }
-            let n = find_permutations_reverse(next_index, adapters, cache);
-            // Cache the solution for 'next_index', this is critical if we don't do this the
-            // solution won't scale to the full problem.
-            cache[next_index] = n;
-            num_permutations += n
+impl Default for ChainOptions {
+    fn default() -> Self {
+        ChainOptions {
+            gaps: [1, 2, 3].iter().copied().collect(),
+            outlet_rating: 0,
+            device_offset: 3,
         }
     }
-    num_permutations
 }
 
-/// Finds the total number of adapter arrangements by working backwards from the device.
-/// This solution uses memoization in order to ensure that the problem can be solved.
-pub fn find_adapter_arrangements(adapters: &[u64]) -> u64 {
-    // Sort the last of adapters to simplify graph construction.
-    let mut sorted_adapters = adapters.to_owned();
-    sorted_adapters.sort_unstable();
-    sorted_adapters.reverse();
-
-    // Create a cache to memoize answers to sub-solutions in.
-    let mut cache = vec![0; adapters.len()];
-    find_permutations_reverse(0, &sorted_adapters, &mut cache)
+/// The result of analyzing how a set of adapters can be chained from the outlet to the device.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AdapterChain {
+    // The number of times every difference occurs in the chain that uses all adapters.
+    pub histogram: BTreeMap<u64, usize>,
+    // Whether every difference in the chain that uses all adapters is an allowed gap.
+    pub complete: bool,
+    // The number of distinct chains from the outlet to the device.
+    pub arrangements: u128,
 }
 
-/// Computes the total number of adapter arrangements by modeling the adapters as vertices in a
-/// graph, and using the maximum difference of 3 constraint to define the edges.
-///
-/// The task is then solved by counting all possible paths from any of the valid start nodes
-/// to the end_node.
+/// Analyzes how the adapters can be chained from the outlet to the device.
 ///
-/// Note that this solution only works as expected on the test input, and doesn't scale to the
-/// full problem.
-pub fn find_adapter_arrangements_graph(adapters: &[u64]) -> u64 {
-    // Sort the last of adapters to simplify graph construction.
-    let mut sorted_adapters = adapters.to_owned();
-    sorted_adapters.sort_unstable();
-
-    // Construct a directed graph of adapters that are allowed to connect to each other.
-    let mut edges: HashMap<u64, Vec<(u64, u64)>> = HashMap::new();
-    for (idx, a1) in sorted_adapters.iter().enumerate() {
-        for a2 in sorted_adapters[idx + 1..].iter() {
-            if a2 - a1 <= 3 {
-                // a1 connects to a2
-                let entry = edges.entry(*a1).or_insert(Vec::new());
-                entry.push((*a1, *a2));
-            }
-        }
+/// The number of arrangements is computed bottom-up over the sorted ratings: the number of
+/// chains that end at an adapter is the sum of the number of chains that end at every lower
+/// rated adapter it can be plugged into. Returns an error if an adapter is rated below the outlet,
+/// or if the number of arrangements does not fit in an u128.
+pub fn analyze_adapters(adapters: &[u64], options: &ChainOptions) -> Result<AdapterChain, String> {
+    if let Some(adapter) = adapters.iter().find(|&&a| a < options.outlet_rating) {
+        return Err(format!(
+            "Adapter rating {} is below the outlet rating {}",
+            adapter, options.outlet_rating
+        ));
     }
-    // Determine the possible starting adapters, i.e. our "start nodes".
-    let mut start_adapters: Vec<u64> = Vec::new();
-    for adapter in sorted_adapters.iter() {
-        if *adapter > 3 {
-            break;
-        }
-        start_adapters.push(*adapter)
+    let mut ratings: Vec<u64> = Vec::with_capacity(adapters.len() + 2);
+    ratings.push(options.outlet_rating);
+    ratings.extend_from_slice(adapters);
+    ratings.sort_unstable();
+    let device_rating = ratings
+        .last()
+        .unwrap()
+        .checked_add(options.device_offset)
+        .ok_or_else(|| "The device rating does not fit in an u64".to_string())?;
+    ratings.push(device_rating);
+
+    let mut histogram: BTreeMap<u64, usize> = BTreeMap::new();
+    for pair in ratings.windows(2) {
+        *histogram.entry(pair[1] - pair[0]).or_insert(0) += 1;
     }
-
-    // Determine the end node, i.e. our chains should end with this value.
-    let end_node = sorted_adapters.last().unwrap();
-
-    // Find all possible paths from any start_node to end_node.
-    let mut arrangements: u64 = 0;
-    for start_node in start_adapters.iter() {
-        // Perform a depth-first search and memoize valid paths
-        let mut visited_nodes: HashSet<u64> = HashSet::new();
-        arrangements += depth_first_search(&edges, &mut visited_nodes, start_node, end_node)
+    let complete = histogram.keys().all(|gap| options.gaps.contains(gap));
+
+    // ways[i] is the number of chains from the outlet that end at ratings[i].
+    let max_gap = options.gaps.iter().next_back().copied().unwrap_or(0);
+    let mut ways: Vec<u128> = vec![0; ratings.len()];
+    ways[0] = 1;
+    for i in 1..ratings.len() {
+        let mut count: u128 = 0;
+        for j in (0..i).rev() {
+            let gap = ratings[i] - ratings[j];
+            if gap > max_gap {
+                break;
+            }
+            if options.gaps.contains(&gap) {
+                count = count
+                    .checked_add(ways[j])
+                    .ok_or_else(|| "The number of arrangements overflows an u128".to_string())?;
+            }
+        }
+        ways[i] = count;
     }
 
-    arrangements
+    Ok(AdapterChain {
+        histogram,
+        complete,
+        arrangements: ways[ratings.len() - 1],
+    })
 }
 
-fn depth_first_search(
-    edges: &HashMap<u64, Vec<(u64, u64)>>,
-    visited_nodes: &mut HashSet<u64>,
-    start_node: &u64,
-    end_node: &u64,
-) -> u64 {
-    let mut path_count = 0;
-    if visited_nodes.contains(start_node) {
-        // Already visited this node. Stop searching.
-        return 0;
-    }
-    visited_nodes.insert(*start_node);
+/// Multiplies the number of 1-jolt differences by the number of 3-jolt differences in the chain
+/// that uses all adapters.
+pub fn test_adapters(adapters: &[u64]) -> i32 {
+    let chain =
+        analyze_adapters(adapters, &ChainOptions::default()).unwrap_or_else(|e| panic!("{}", e));
+    let count = |gap: u64| chain.histogram.get(&gap).copied().unwrap_or(0) as i32;
+    count(1) * count(3)
+}
 
-    if start_node == end_node {
-        // Found a new path.
-        visited_nodes.remove(start_node);
-        return 1;
-    }
+/// Finds the total number of adapter arrangements, see `analyze_adapters`.
+pub fn find_adapter_arrangements(adapters: &[u64]) -> u64 {
+    let chain =
+        analyze_adapters(adapters, &ChainOptions::default()).unwrap_or_else(|e| panic!("{}", e));
+    u64::try_from(chain.arrangements)
+        .unwrap_or_else(|_| panic!("The number of arrangements does not fit in an u64"))
+}
 
-    // Check if this start_node has any neighbours.
-    if let Some(neighbours) = edges.get(start_node) {
-        // Recursively search using the neighbours.
-        for neighbour in neighbours.iter() {
-            path_count += depth_first_search(edges, visited_nodes, &neighbour.1, end_node);
-        }
-    }
-    visited_nodes.remove(start_node);
-    path_count
+/// Finds the total number of adapter arrangements, this is an alias of
+/// `find_adapter_arrangements`.
+pub fn find_adapter_arrangements_graph(adapters: &[u64]) -> u64 {
+    find_adapter_arrangements(adapters)
 }
 
 #[cfg(test)]
@@ -226,4 +189,52 @@ mod tests {
         assert_eq!(find_adapter_arrangements(&ratings), 19208);
         assert_eq!(find_adapter_arrangements_graph(&ratings), 19208);
     }
+
+    #[test]
+    fn test_analyze_adapters() {
+        let ratings = get_test_input();
+        let chain = analyze_adapters(&ratings, &ChainOptions::default()).unwrap();
+        assert_eq!(chain.histogram, [(1, 7), (3, 5)].iter().copied().collect());
+        assert!(chain.complete);
+        assert_eq!(chain.arrangements, 8);
+
+        // Without 1-jolt differences, the product is zero instead of a panic.
+        assert_eq!(test_adapters(&[3, 6, 9]), 0);
+        assert_eq!(test_adapters(&[]), 0);
+
+        let options = ChainOptions {
+            gaps: [1, 3].iter().copied().collect(),
+            ..ChainOptions::default()
+        };
+        // A gap of 2 can not be bridged if only gaps of 1 and 3 are allowed, but 1 and 3 can
+        // each be skipped.
+        let chain = analyze_adapters(&[1, 3, 4], &options).unwrap();
+        assert!(!chain.complete);
+        assert_eq!(chain.arrangements, 2);
+
+        let options = ChainOptions {
+            outlet_rating: 10,
+            device_offset: 5,
+            gaps: [5].iter().copied().collect(),
+        };
+        let chain = analyze_adapters(&[15, 20, 25], &options).unwrap();
+        assert_eq!(chain.histogram, [(5, 4)].iter().copied().collect());
+        assert_eq!(chain.arrangements, 1);
+        assert!(analyze_adapters(&[5], &options).is_err());
+    }
+
+    #[test]
+    fn test_analyze_adapters_overflow() {
+        // Every adapter can be reached from the three before it, so the number of arrangements
+        // grows like the tribonacci numbers.
+        let adapters: Vec<u64> = (1..=100).collect();
+        let chain = analyze_adapters(&adapters, &ChainOptions::default()).unwrap();
+        assert_eq!(chain.arrangements, 180_396_380_815_100_901_214_157_639);
+
+        let adapters: Vec<u64> = (1..=200).collect();
+        assert_eq!(
+            analyze_adapters(&adapters, &ChainOptions::default()),
+            Err("The number of arrangements overflows an u128".to_string())
+        );
+    }
 }
@@ -27,19 +27,15 @@ pub fn seat_individuals_1(seating_plan: &[Vec<char>]) -> Vec<Vec<char>> {
     for row_index in 0..seating_plan.len() {
         for col_index in 0..seating_plan[row_index].len() {
             match seating_plan[row_index][col_index] {
-                'L' => {
+                'L' if count_occupied_adjacent_seats(seating_plan, row_index, col_index) == 0 => {
                     // Seat is empty, the seat becomes occupied if there is no seat
                     // adjacent to this seat that is occupied.
-                    if count_occupied_adjacent_seats(&seating_plan, row_index, col_index) == 0 {
-                        updated_seating_plan[row_index][col_index] = '#';
-                    }
+                    updated_seating_plan[row_index][col_index] = '#';
                 }
-                '#' => {
+                '#' if count_occupied_adjacent_seats(seating_plan, row_index, col_index) >= 4 => {
                     // Seat is occupied. If four or more seats are adjacent to it are also occupied,
                     // the seat becomes empty.
-                    if count_occupied_adjacent_seats(&seating_plan, row_index, col_index) >= 4 {
-                        updated_seating_plan[row_index][col_index] = 'L';
-                    }
+                    updated_seating_plan[row_index][col_index] = 'L';
                 }
                 _ => {}
             }
@@ -55,19 +51,15 @@ pub fn seat_individuals_2(seating_plan: &[Vec<char>]) -> Vec<Vec<char>> {
     for row_index in 0..seating_plan.len() {
         for col_index in 0..seating_plan[row_index].len() {
             match seating_plan[row_index][col_index] {
-                'L' => {
+                'L' if count_occupied_seats_directions(seating_plan, row_index, col_index) == 0 => {
                     // Seat is empty, the seat becomes occupied if there is no seat in any
                     // direction that is occupied.
-                    if count_occupied_seats_directions(&seating_plan, row_index, col_index) == 0 {
-                        updated_seating_plan[row_index][col_index] = '#';
-                    }
+                    updated_seating_plan[row_index][col_index] = '#';
                 }
-                '#' => {
+                '#' if count_occupied_seats_directions(seating_plan, row_index, col_index) >= 5 => {
                     // Seat is occupied. If five or more seats are adjacent to it are also occupied,
                     // the seat becomes empty.
-                    if count_occupied_seats_directions(&seating_plan, row_index, col_index) >= 5 {
-                        updated_seating_plan[row_index][col_index] = 'L';
-                    }
+                    updated_seating_plan[row_index][col_index] = 'L';
                 }
                 _ => {}
             }
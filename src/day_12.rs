@@ -98,10 +98,10 @@ impl Ship {
             Direction::EAST => self.x += instruction.value,
             Direction::WEST => self.x -= instruction.value,
             Direction::LEFT => {
-                self.rotate(-instruction.value as isize);
+                self.rotate(-instruction.value);
             }
             Direction::RIGHT => {
-                self.rotate(instruction.value as isize);
+                self.rotate(instruction.value);
             }
             Direction::FORWARD => {
                 // Translate the instruction and invoke this method again.
@@ -196,38 +196,21 @@ pub fn read_instructions<R: BufRead>(reader: &mut R) -> Vec<NavigationInstructio
         }
 
         // Split the instruction
-        let direction: Direction;
-        let value: isize;
-
         let mut iter = line.chars();
-        match iter.next() {
-            Some('N') => {
-                direction = Direction::NORTH;
-            }
-            Some('S') => {
-                direction = Direction::SOUTH;
-            }
-            Some('W') => {
-                direction = Direction::WEST;
-            }
-            Some('E') => {
-                direction = Direction::EAST;
-            }
-            Some('L') => {
-                direction = Direction::LEFT;
-            }
-            Some('F') => {
-                direction = Direction::FORWARD;
-            }
-            Some('R') => {
-                direction = Direction::RIGHT;
-            }
+        let direction = match iter.next() {
+            Some('N') => Direction::NORTH,
+            Some('S') => Direction::SOUTH,
+            Some('W') => Direction::WEST,
+            Some('E') => Direction::EAST,
+            Some('L') => Direction::LEFT,
+            Some('F') => Direction::FORWARD,
+            Some('R') => Direction::RIGHT,
             _ => {
                 panic!("Unable to parse instruction: {}", line);
             }
-        }
+        };
 
-        value = iter
+        let value: isize = iter
             .collect::<String>()
             .parse::<isize>()
             .unwrap_or_else(|e| panic!("Unable to parse instruction '{}': {}", line, e));
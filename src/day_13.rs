@@ -58,7 +58,7 @@ pub fn find_earliest_bus_departure_time(schedule: &[String]) -> Option<i64> {
 
         let b = bus_schedule
             .parse::<i64>()
-            .expect(&*format!("Expected '{}' to be an integer.", bus_schedule));
+            .unwrap_or_else(|_| panic!("Expected '{}' to be an integer.", bus_schedule));
         divisors.push(b);
         remainders.push(b - index as i64);
     }
@@ -106,7 +106,6 @@ pub fn chinese_remainder_theorem(divisors: &[i64], remainders: &[i64]) -> Option
 
 /// Reads the input for part 1 of the exercise.
 pub fn read_input_1<R: BufRead>(reader: &mut R) -> (isize, HashSet<isize>) {
-    let arrival_time;
     let mut schedule_times = HashSet::new();
 
     // Read the expected departure time.
@@ -117,10 +116,10 @@ pub fn read_input_1<R: BufRead>(reader: &mut R) -> (isize, HashSet<isize>) {
 
     let line = buffer.trim();
 
-    match line.parse::<isize>() {
-        Ok(d) => arrival_time = d,
+    let arrival_time = match line.parse::<isize>() {
+        Ok(d) => d,
         Err(e) => panic!("Could not parse departure time '{}': {}", line, e),
-    }
+    };
 
     // Read the schedules
     buffer = String::new();
@@ -168,7 +167,7 @@ mod tests {
     fn get_test_input_reader() -> BufReader<&'static [u8]> {
         let input = "939
 7,13,x,x,59,x,31,19";
-        return BufReader::new(input.as_bytes());
+        BufReader::new(input.as_bytes())
     }
 
     #[test]
@@ -201,8 +200,8 @@ mod tests {
             find_earliest_bus_departure_time(
                 &(vec!["7", "13", "x", "x", "59", "x", "31", "19"])
                     .into_iter()
-                    .map(|s| String::from(s))
-                    .collect()
+                    .map(String::from)
+                    .collect::<Vec<String>>()
             ),
             Some(1068781)
         );
@@ -210,8 +209,8 @@ mod tests {
             find_earliest_bus_departure_time(
                 &(vec!["17", "x", "13", "19"])
                     .into_iter()
-                    .map(|s| String::from(s))
-                    .collect()
+                    .map(String::from)
+                    .collect::<Vec<String>>()
             ),
             Some(3417)
         );
@@ -219,8 +218,8 @@ mod tests {
             find_earliest_bus_departure_time(
                 &(vec!["67", "7", "59", "61"])
                     .into_iter()
-                    .map(|s| String::from(s))
-                    .collect()
+                    .map(String::from)
+                    .collect::<Vec<String>>()
             ),
             Some(754018)
         );
@@ -228,8 +227,8 @@ mod tests {
             find_earliest_bus_departure_time(
                 &(vec!["67", "x", "7", "59", "61"])
                     .into_iter()
-                    .map(|s| String::from(s))
-                    .collect()
+                    .map(String::from)
+                    .collect::<Vec<String>>()
             ),
             Some(779210)
         );
@@ -237,8 +236,8 @@ mod tests {
             find_earliest_bus_departure_time(
                 &(vec!["67", "7", "x", "59", "61"])
                     .into_iter()
-                    .map(|s| String::from(s))
-                    .collect()
+                    .map(String::from)
+                    .collect::<Vec<String>>()
             ),
             Some(1261476)
         );
@@ -246,8 +245,8 @@ mod tests {
             find_earliest_bus_departure_time(
                 &(vec!["1789", "37", "47", "1889"])
                     .into_iter()
-                    .map(|s| String::from(s))
-                    .collect()
+                    .map(String::from)
+                    .collect::<Vec<String>>()
             ),
             Some(1202161486)
         );
@@ -47,7 +47,7 @@ impl TryFrom<&str> for PasswordPolicy {
             static ref POLICY_PATTERN: Regex =
                 Regex::new(r"^([0-9]+)-([0-9]+) ([a-z0-9]{1}): (.*)$").unwrap();
         }
-        match POLICY_PATTERN.captures(&value) {
+        match POLICY_PATTERN.captures(value) {
             None => Err("Input did not match the expected format."),
             Some(captures) => Ok(PasswordPolicy {
                 minimum_occurences: captures[1].parse::<i32>().unwrap(),
@@ -76,10 +76,8 @@ pub fn read_password_policies() -> Vec<PasswordPolicy> {
             break;
         }
 
-        let policy = PasswordPolicy::try_from(buffer.trim()).expect(&*format!(
-            "Error: '{}' is not a valid password policy.",
-            buffer
-        ));
+        let policy = PasswordPolicy::try_from(buffer.trim())
+            .unwrap_or_else(|_| panic!("Error: '{}' is not a valid password policy.", buffer));
         password_policies.push(policy);
     }
 
@@ -111,7 +109,7 @@ mod tests {
             value: 'a',
             password: "abcde".to_string(),
         };
-        assert_eq!(policy.is_valid_1(), true);
+        assert!(policy.is_valid_1());
 
         let policy = PasswordPolicy {
             minimum_occurences: 1,
@@ -119,7 +117,7 @@ mod tests {
             value: 'c',
             password: "abde".to_string(),
         };
-        assert_eq!(policy.is_valid_1(), false);
+        assert!(!policy.is_valid_1());
     }
 
     #[test]
@@ -130,7 +128,7 @@ mod tests {
             value: 'a',
             password: "abcde".to_string(),
         };
-        assert_eq!(policy.is_valid_2(), true);
+        assert!(policy.is_valid_2());
 
         let policy = PasswordPolicy {
             minimum_occurences: 1,
@@ -139,7 +137,7 @@ mod tests {
             password: "cabcde".to_string(),
         };
         // both index 1, and index 4 are "c" which is invalid
-        assert_eq!(policy.is_valid_2(), false);
+        assert!(!policy.is_valid_2());
 
         let policy = PasswordPolicy {
             minimum_occurences: 1,
@@ -147,6 +145,6 @@ mod tests {
             value: 'c',
             password: "ca".to_string(),
         };
-        assert_eq!(policy.is_valid_2(), true);
+        assert!(policy.is_valid_2());
     }
 }
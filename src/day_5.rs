@@ -1,3 +1,420 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// Describes why a boarding pass could not be decoded in strict mode.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BoardingPassError {
+    // The character at `index` is not part of the layout's alphabet.
+    UnknownCharacter { index: usize, character: char },
+    // A row letter at `index` appeared after the first column letter.
+    RowAfterColumn { index: usize, character: char },
+    // The boarding pass contains the wrong number of row instructions.
+    RowCount { expected: u32, found: u32 },
+    // The boarding pass contains the wrong number of column instructions.
+    ColumnCount { expected: u32, found: u32 },
+    // The boarding pass decodes to a seat that does not exist in the layout.
+    InvalidSeat { row: i32, col: i32 },
+    // The aircraft dimensions do not describe a valid layout.
+    InvalidLayout { num_rows: i32, num_cols: i32 },
+}
+
+impl fmt::Display for BoardingPassError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardingPassError::UnknownCharacter { index, character } => {
+                write!(f, "Unknown character '{}' at index {}.", character, index)
+            }
+            BoardingPassError::RowAfterColumn { index, character } => write!(
+                f,
+                "Row letter '{}' at index {} appears after a column letter.",
+                character, index
+            ),
+            BoardingPassError::RowCount { expected, found } => write!(
+                f,
+                "Expected {} row instructions, but found {}.",
+                expected, found
+            ),
+            BoardingPassError::ColumnCount { expected, found } => write!(
+                f,
+                "Expected {} column instructions, but found {}.",
+                expected, found
+            ),
+            BoardingPassError::InvalidSeat { row, col } => write!(
+                f,
+                "Row {}, column {} is not a seat in this aircraft.",
+                row, col
+            ),
+            BoardingPassError::InvalidLayout { num_rows, num_cols } => write!(
+                f,
+                "An aircraft with {} rows and {} columns is not a valid layout.",
+                num_rows, num_cols
+            ),
+        }
+    }
+}
+
+/// Describes the seating layout of an aircraft and how boarding passes map onto it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AircraftLayout {
+    // The number of rows in the aircraft.
+    pub num_rows: i32,
+    // The number of columns (seats per row) in the aircraft.
+    pub num_cols: i32,
+    // The number of row instructions on a boarding pass. This allows for row counts that are not
+    // a power of two, in which case some encodable rows simply don't exist. At most 31, such that
+    // every row fits in an i32.
+    pub row_bits: u32,
+    // The number of column instructions on a boarding pass, at most 31.
+    pub col_bits: u32,
+    // The letters that select the (lower, upper) half of the rows.
+    pub row_letters: (char, char),
+    // The letters that select the (lower, upper) half of the columns.
+    pub col_letters: (char, char),
+    // Positions of seats that are blocked, or that don't exist in this aircraft.
+    pub blocked_seats: HashSet<(i32, i32)>,
+    // The seat id is computed as `row * seat_id_multiplier + column`, must be positive.
+    pub seat_id_multiplier: i32,
+}
+
+impl AircraftLayout {
+    /// Creates a layout for an aircraft with `num_rows` rows and `num_cols` columns using the
+    /// `F/B` and `L/R` letters, and the smallest bit widths that can address every seat.
+    ///
+    /// Returns an error if the aircraft has no rows or no columns, or if a row or column can't be
+    /// decoded from a boarding pass without overflowing an i32.
+    pub fn new(num_rows: i32, num_cols: i32) -> Result<Self, String> {
+        if num_rows < 1 || num_cols < 1 {
+            return Err(format!(
+                "An aircraft needs at least one row and one column, got {} rows and {} columns.",
+                num_rows, num_cols
+            ));
+        }
+        let row_bits = bits_required(num_rows);
+        let col_bits = bits_required(num_cols);
+        if row_bits > MAX_BITS || col_bits > MAX_BITS {
+            return Err(format!(
+                "Boarding passes may have at most {} row and column instructions, got {} and {}.",
+                MAX_BITS, row_bits, col_bits
+            ));
+        }
+        Ok(AircraftLayout {
+            num_rows,
+            num_cols,
+            row_bits,
+            col_bits,
+            row_letters: ('F', 'B'),
+            col_letters: ('L', 'R'),
+            blocked_seats: HashSet::new(),
+            seat_id_multiplier: num_cols,
+        })
+    }
+
+    /// Decodes the boarding pass into the position of the seat as a (row, column) tuple.
+    ///
+    /// Characters that are not part of the layout's alphabet are ignored.
+    pub fn decode(&self, boarding_pass: &str) -> (i32, i32) {
+        let mut row_instructions: Vec<bool> = Vec::new();
+        let mut column_instructions: Vec<bool> = Vec::new();
+        for c in boarding_pass.chars() {
+            match c {
+                _ if c == self.row_letters.0 => row_instructions.push(false),
+                _ if c == self.row_letters.1 => row_instructions.push(true),
+                _ if c == self.col_letters.0 => column_instructions.push(false),
+                _ if c == self.col_letters.1 => column_instructions.push(true),
+                _ => {}
+            }
+        }
+        (
+            decode_bits(&row_instructions, self.row_bits),
+            decode_bits(&column_instructions, self.col_bits),
+        )
+    }
+
+    /// Strictly decodes the boarding pass into the position of the seat as a (row, column) tuple.
+    ///
+    /// Unlike `decode`, this rejects unknown characters, row letters that follow column letters,
+    /// the wrong number of row or column instructions and seats that don't exist in the layout.
+    pub fn decode_strict(&self, boarding_pass: &str) -> Result<(i32, i32), BoardingPassError> {
+        let mut row_instructions: Vec<bool> = Vec::new();
+        let mut column_instructions: Vec<bool> = Vec::new();
+        for (index, c) in boarding_pass.chars().enumerate() {
+            match c {
+                _ if c == self.row_letters.0 || c == self.row_letters.1 => {
+                    if !column_instructions.is_empty() {
+                        return Err(BoardingPassError::RowAfterColumn {
+                            index,
+                            character: c,
+                        });
+                    }
+                    row_instructions.push(c == self.row_letters.1);
+                }
+                _ if c == self.col_letters.0 || c == self.col_letters.1 => {
+                    column_instructions.push(c == self.col_letters.1);
+                }
+                _ => {
+                    return Err(BoardingPassError::UnknownCharacter {
+                        index,
+                        character: c,
+                    })
+                }
+            }
+        }
+
+        if row_instructions.len() != self.row_bits as usize {
+            return Err(BoardingPassError::RowCount {
+                expected: self.row_bits,
+                found: row_instructions.len() as u32,
+            });
+        }
+        if column_instructions.len() != self.col_bits as usize {
+            return Err(BoardingPassError::ColumnCount {
+                expected: self.col_bits,
+                found: column_instructions.len() as u32,
+            });
+        }
+
+        let position = (
+            decode_bits(&row_instructions, self.row_bits),
+            decode_bits(&column_instructions, self.col_bits),
+        );
+        if !self.is_seat(position) {
+            return Err(BoardingPassError::InvalidSeat {
+                row: position.0,
+                col: position.1,
+            });
+        }
+        Ok(position)
+    }
+
+    /// Encodes the position of a seat into a boarding pass.
+    ///
+    /// Returns an error if the seat does not exist in this layout.
+    pub fn encode(&self, seat_position: (i32, i32)) -> Result<String, String> {
+        self.validate(seat_position)?;
+        let mut boarding_pass = String::new();
+        for bit in (0..self.row_bits).rev() {
+            boarding_pass.push(if (seat_position.0 >> bit) & 1 == 1 {
+                self.row_letters.1
+            } else {
+                self.row_letters.0
+            });
+        }
+        for bit in (0..self.col_bits).rev() {
+            boarding_pass.push(if (seat_position.1 >> bit) & 1 == 1 {
+                self.col_letters.1
+            } else {
+                self.col_letters.0
+            });
+        }
+        Ok(boarding_pass)
+    }
+
+    /// Validates that the seat at the given position exists and is not blocked.
+    pub fn validate(&self, seat_position: (i32, i32)) -> Result<(), String> {
+        let (row, col) = seat_position;
+        if row < 0 || row >= self.num_rows {
+            return Err(format!(
+                "Row {} is outside of the aircraft, which has {} rows.",
+                row, self.num_rows
+            ));
+        }
+        if col < 0 || col >= self.num_cols {
+            return Err(format!(
+                "Column {} is outside of the aircraft, which has {} columns.",
+                col, self.num_cols
+            ));
+        }
+        if self.blocked_seats.contains(&seat_position) {
+            return Err(format!(
+                "Seat at row {}, column {} is blocked or does not exist.",
+                row, col
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns whether the seat at the given position exists and is not blocked.
+    pub fn is_seat(&self, seat_position: (i32, i32)) -> bool {
+        self.validate(seat_position).is_ok()
+    }
+
+    /// Returns the identifier of the seat given its position.
+    pub fn seat_id(&self, seat_position: (i32, i32)) -> i32 {
+        seat_position.0 * self.seat_id_multiplier + seat_position.1
+    }
+
+    /// Returns the position of the seat given its identifier.
+    pub fn seat_position(&self, seat_id: i32) -> (i32, i32) {
+        (
+            seat_id.div_euclid(self.seat_id_multiplier),
+            seat_id.rem_euclid(self.seat_id_multiplier),
+        )
+    }
+}
+
+impl Default for AircraftLayout {
+    /// The layout used by the puzzle: 128 rows of 8 seats.
+    fn default() -> Self {
+        AircraftLayout::new(128, 8).unwrap()
+    }
+}
+
+/// The maximum number of row or column instructions on a boarding pass, such that the decoded
+/// row or column fits in an i32.
+const MAX_BITS: u32 = 31;
+
+/// Returns the number of bits required to address `num_locations` distinct locations.
+fn bits_required(num_locations: i32) -> u32 {
+    let mut bits = 0;
+    while (1i64 << bits) < num_locations as i64 {
+        bits += 1;
+    }
+    bits
+}
+
+/// Decodes a sequence of instructions as a binary number of `num_bits` bits, where `true` is
+/// interpreted as a 1 (upper half) and `false` as a 0 (lower half).
+///
+/// Missing instructions are interpreted as selecting the lower half.
+fn decode_bits(instructions: &[bool], num_bits: u32) -> i32 {
+    let mut value = 0;
+    for bit in 0..num_bits as usize {
+        value <<= 1;
+        if instructions.get(bit) == Some(&true) {
+            value |= 1;
+        }
+    }
+    value
+}
+
+/// Tracks which seats in an aircraft are occupied, based on a set of boarding passes.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SeatMap {
+    // The layout of the aircraft.
+    pub layout: AircraftLayout,
+    // The boarding passes that were issued for every occupied seat, keyed on the seat position.
+    occupants: BTreeMap<(i32, i32), Vec<String>>,
+    // Boarding passes that could not be decoded strictly, together with the reason why.
+    invalid_passes: Vec<(String, BoardingPassError)>,
+}
+
+impl SeatMap {
+    /// Builds a seat map by strictly decoding every boarding pass using the provided `layout`.
+    ///
+    /// Boarding passes that can't be decoded don't occupy a seat, they are collected separately
+    /// in `invalid_passes`.
+    pub fn from_boarding_passes<'a, I>(layout: AircraftLayout, boarding_passes: I) -> Self
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut occupants: BTreeMap<(i32, i32), Vec<String>> = BTreeMap::new();
+        let mut invalid_passes: Vec<(String, BoardingPassError)> = Vec::new();
+        for boarding_pass in boarding_passes {
+            match layout.decode_strict(boarding_pass) {
+                Ok(position) => occupants
+                    .entry(position)
+                    .or_default()
+                    .push(boarding_pass.to_string()),
+                Err(e) => invalid_passes.push((boarding_pass.to_string(), e)),
+            }
+        }
+        SeatMap {
+            layout,
+            occupants,
+            invalid_passes,
+        }
+    }
+
+    /// Returns whether the seat at the given position has been claimed by a boarding pass.
+    pub fn is_occupied(&self, seat_position: (i32, i32)) -> bool {
+        self.occupants.contains_key(&seat_position)
+    }
+
+    /// Returns the positions of all seats that exist in the layout, but that are not occupied,
+    /// ordered by seat id.
+    pub fn missing_seats(&self) -> Vec<(i32, i32)> {
+        let mut missing: Vec<(i32, i32)> = Vec::new();
+        for row in 0..self.layout.num_rows {
+            for col in 0..self.layout.num_cols {
+                if self.layout.is_seat((row, col)) && !self.is_occupied((row, col)) {
+                    missing.push((row, col));
+                }
+            }
+        }
+        missing.sort_by_key(|position| self.layout.seat_id(*position));
+        missing
+    }
+
+    /// Returns the positions of the missing seats for which the seats with ids directly before
+    /// and after them are both occupied.
+    pub fn enclosed_missing_seats(&self) -> Vec<(i32, i32)> {
+        self.missing_seats()
+            .into_iter()
+            .filter(|position| self.is_enclosed(*position))
+            .collect()
+    }
+
+    /// Returns the seats that were claimed by more than one boarding pass, together with the
+    /// boarding passes that claimed them.
+    pub fn duplicates(&self) -> Vec<((i32, i32), &[String])> {
+        self.occupants
+            .iter()
+            .filter(|(_, passes)| passes.len() > 1)
+            .map(|(position, passes)| (*position, passes.as_slice()))
+            .collect()
+    }
+
+    /// Returns the boarding passes that could not be mapped onto a seat in the layout, together
+    /// with the reason why.
+    pub fn invalid_passes(&self) -> &[(String, BoardingPassError)] {
+        &self.invalid_passes
+    }
+
+    /// Tests whether the neighbours (by seat id) of the seat at `seat_position` are occupied.
+    fn is_enclosed(&self, seat_position: (i32, i32)) -> bool {
+        let seat_id = self.layout.seat_id(seat_position);
+        self.is_occupied(self.layout.seat_position(seat_id - 1))
+            && self.is_occupied(self.layout.seat_position(seat_id + 1))
+    }
+}
+
+impl fmt::Display for SeatMap {
+    /// Renders the cabin as ASCII art with one line per row, where:
+    ///
+    /// - `#` is an occupied seat.
+    /// - `D` is a seat that was claimed by more than one boarding pass.
+    /// - `.` is a missing seat.
+    /// - `O` is a missing seat of which both neighbours are occupied.
+    /// - `X` is a blocked, or nonexistent seat.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let row_label_width = (self.layout.num_rows - 1).max(0).to_string().len();
+        let col_label_width = (self.layout.num_cols - 1).max(0).to_string().len();
+
+        // Column labels.
+        write!(f, "{:width$}", "", width = row_label_width)?;
+        for col in 0..self.layout.num_cols {
+            write!(f, " {:>width$}", col, width = col_label_width)?;
+        }
+        writeln!(f)?;
+
+        for row in 0..self.layout.num_rows {
+            write!(f, "{:>width$}", row, width = row_label_width)?;
+            for col in 0..self.layout.num_cols {
+                let symbol = match self.occupants.get(&(row, col)) {
+                    Some(passes) if passes.len() > 1 => 'D',
+                    Some(_) => '#',
+                    None if !self.layout.is_seat((row, col)) => 'X',
+                    None if self.is_enclosed((row, col)) => 'O',
+                    None => '.',
+                };
+                write!(f, " {:>width$}", symbol, width = col_label_width)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
 /// Parses the boarding pass and returns the position of the seat as a tuple where
 /// the first entry represents the row and the second the column.
 pub fn parse_boarding_pass(boarding_pass: &str, num_rows: i32, num_cols: i32) -> (i32, i32) {
@@ -26,6 +443,20 @@ pub fn parse_boarding_pass(boarding_pass: &str, num_rows: i32, num_cols: i32) ->
     )
 }
 
+/// Strictly parses the boarding pass and returns the position of the seat as a tuple where
+/// the first entry represents the row and the second the column.
+///
+/// See `AircraftLayout::decode_strict` for the checks that are performed.
+pub fn parse_boarding_pass_strict(
+    boarding_pass: &str,
+    num_rows: i32,
+    num_cols: i32,
+) -> Result<(i32, i32), BoardingPassError> {
+    AircraftLayout::new(num_rows, num_cols)
+        .map_err(|_| BoardingPassError::InvalidLayout { num_rows, num_cols })?
+        .decode_strict(boarding_pass)
+}
+
 /// Finds the location of split given a vector of instructions.
 ///
 /// The vector of instructions is interpreted as follows:
@@ -46,9 +477,9 @@ pub fn find_split_position(instructions: Vec<bool>, num_locations: i32) -> i32 {
     start
 }
 
-/// Returns the identifier of the seat given its position.
+/// Returns the identifier of the seat given its position in the default aircraft layout.
 pub fn seat_id(seat_position: (i32, i32)) -> i32 {
-    seat_position.0 * 8 + seat_position.1
+    AircraftLayout::default().seat_id(seat_position)
 }
 
 #[cfg(test)]
@@ -63,6 +494,46 @@ mod tests {
         assert_eq!(parse_boarding_pass("FFFBBBFRRR", 128, 8), (14, 7));
     }
 
+    #[test]
+    fn test_parse_boarding_pass_strict() {
+        assert_eq!(
+            parse_boarding_pass_strict("FBFBBFFRLR", 128, 8),
+            Ok((44, 5))
+        );
+        assert_eq!(
+            parse_boarding_pass_strict("FBFBXFFRLR", 128, 8),
+            Err(BoardingPassError::UnknownCharacter {
+                index: 4,
+                character: 'X'
+            })
+        );
+        assert_eq!(
+            parse_boarding_pass_strict("FFFF", 128, 8),
+            Err(BoardingPassError::RowCount {
+                expected: 7,
+                found: 4
+            })
+        );
+        assert_eq!(
+            parse_boarding_pass_strict("FBFBBFFRL", 128, 8),
+            Err(BoardingPassError::ColumnCount {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            parse_boarding_pass_strict("FBFBBFRFLR", 128, 8),
+            Err(BoardingPassError::RowAfterColumn {
+                index: 7,
+                character: 'F'
+            })
+        );
+        assert_eq!(
+            parse_boarding_pass_strict("BBBBBBBLLL", 100, 8),
+            Err(BoardingPassError::InvalidSeat { row: 127, col: 0 })
+        );
+    }
+
     #[test]
     fn test_find_split_position() {
         assert_eq!(
@@ -75,4 +546,123 @@ mod tests {
     fn test_parse_seat_id() {
         assert_eq!(seat_id((44, 5)), 357);
     }
+
+    #[test]
+    fn test_layout_decode() {
+        let layout = AircraftLayout::default();
+        assert_eq!(layout.decode("FBFBBFFRLR"), (44, 5));
+        assert_eq!(layout.decode("BBFFBBFRLL"), (102, 4));
+        assert_eq!(layout.seat_id(layout.decode("BFFFBBFRRR")), 567);
+        assert_eq!(layout.seat_position(567), (70, 7));
+
+        // A smaller aircraft with 100 rows, 6 seats per row and a different alphabet.
+        let mut layout = AircraftLayout::new(100, 6).unwrap();
+        layout.row_letters = ('0', '1');
+        layout.col_letters = ('a', 'b');
+        assert_eq!(layout.row_bits, 7);
+        assert_eq!(layout.col_bits, 3);
+        assert_eq!(layout.decode("1100011bab"), (99, 5));
+        assert_eq!(layout.seat_id((99, 5)), 599);
+    }
+
+    #[test]
+    fn test_layout_new() {
+        assert!(AircraftLayout::new(0, 8).is_err());
+        assert!(AircraftLayout::new(128, 0).is_err());
+        assert!(AircraftLayout::new(-1, 8).is_err());
+        let layout = AircraftLayout::new(i32::MAX, 1).unwrap();
+        assert_eq!(layout.row_bits, 31);
+        assert_eq!(layout.decode(&"B".repeat(31)), (i32::MAX, 0));
+        assert_eq!(
+            parse_boarding_pass_strict("FBFBBFFRLR", 128, 0),
+            Err(BoardingPassError::InvalidLayout {
+                num_rows: 128,
+                num_cols: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_layout_encode_and_validate() {
+        let mut layout = AircraftLayout::new(100, 6).unwrap();
+        assert_eq!(layout.encode((44, 5)).unwrap(), "FBFBBFFRLR");
+        assert!(layout.encode((100, 0)).is_err());
+        assert!(layout.encode((0, 6)).is_err());
+
+        layout.blocked_seats.insert((0, 0));
+        assert!(layout.validate((0, 0)).is_err());
+        assert!(layout.is_seat((0, 1)));
+        assert!(!layout.is_seat((-1, 1)));
+
+        for row in 0..layout.num_rows {
+            for col in 0..layout.num_cols {
+                match layout.encode((row, col)) {
+                    Ok(boarding_pass) => assert_eq!(layout.decode(&boarding_pass), (row, col)),
+                    Err(_) => assert_eq!((row, col), (0, 0)),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_seat_map() {
+        let mut layout = AircraftLayout::new(4, 4).unwrap();
+        layout.blocked_seats.insert((0, 0));
+        let boarding_passes = vec![
+            "FFLR", "FFRL", "FFRR", // Row 0
+            "FBLL", "FBRL", "FBRR", // Row 1
+            "BFLL", "BFLR", "BFRL", "BFRL", // Row 2, with a duplicate
+            "BBRR", // Row 3
+        ];
+        let map = SeatMap::from_boarding_passes(layout, boarding_passes);
+
+        assert_eq!(
+            map.missing_seats(),
+            vec!((1, 1), (2, 3), (3, 0), (3, 1), (3, 2))
+        );
+        assert_eq!(map.enclosed_missing_seats(), vec!((1, 1)));
+        assert_eq!(
+            map.duplicates(),
+            vec!(((2, 2), &["BFRL".to_string(), "BFRL".to_string()][..]))
+        );
+        assert!(map.invalid_passes().is_empty());
+        assert_eq!(
+            map.to_string(),
+            "  0 1 2 3
+0 X # # #
+1 # O # #
+2 # # D .
+3 . . . #
+"
+        );
+
+        // Malformed boarding passes don't occupy a seat.
+        let mut layout = AircraftLayout::new(4, 4).unwrap();
+        layout.blocked_seats.insert((0, 0));
+        let map = SeatMap::from_boarding_passes(layout, vec!["FFFF", "FxFLR", "FFLL", "FBLR"]);
+        assert_eq!(map.missing_seats().len(), 14);
+        assert_eq!(
+            map.invalid_passes(),
+            &[
+                (
+                    "FFFF".to_string(),
+                    BoardingPassError::RowCount {
+                        expected: 2,
+                        found: 4
+                    }
+                ),
+                (
+                    "FxFLR".to_string(),
+                    BoardingPassError::UnknownCharacter {
+                        index: 1,
+                        character: 'x'
+                    }
+                ),
+                (
+                    "FFLL".to_string(),
+                    BoardingPassError::InvalidSeat { row: 0, col: 0 }
+                ),
+            ][..]
+        );
+    }
 }
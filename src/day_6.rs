@@ -1,5 +1,449 @@
-use std::collections::{HashMap, HashSet};
-use std::io::BufRead;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+/// A set of answers, stored as a bitset where every bit represents a single question.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct AnswerSet {
+    words: Vec<u64>,
+}
+
+impl AnswerSet {
+    /// Creates an empty set that can hold answers to `width` questions.
+    pub fn new(width: usize) -> Self {
+        AnswerSet {
+            words: vec![0; width.div_ceil(64)],
+        }
+    }
+
+    /// Creates a set in which all `width` questions were answered with `yes`.
+    pub fn full(width: usize) -> Self {
+        let mut set = AnswerSet::new(width);
+        for question in 0..width {
+            set.insert(question);
+        }
+        set
+    }
+
+    /// Marks `question` as answered with `yes`.
+    ///
+    /// Questions that do not fit in the width the set was created with are ignored, just like
+    /// `contains` reports them as not answered.
+    pub fn insert(&mut self, question: usize) {
+        if let Some(word) = self.words.get_mut(question / 64) {
+            *word |= 1 << (question % 64);
+        }
+    }
+
+    /// Tests whether `question` was answered with `yes`.
+    pub fn contains(&self, question: usize) -> bool {
+        match self.words.get(question / 64) {
+            Some(word) => word & (1 << (question % 64)) != 0,
+            None => false,
+        }
+    }
+
+    /// Returns the number of questions that were answered with `yes`.
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Returns whether no question was answered with `yes`.
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// Returns the questions that were answered with `yes` in ascending order.
+    pub fn questions(&self) -> Vec<usize> {
+        let mut questions = Vec::new();
+        for (idx, word) in self.words.iter().enumerate() {
+            let mut word = *word;
+            while word != 0 {
+                questions.push(idx * 64 + word.trailing_zeros() as usize);
+                word &= word - 1;
+            }
+        }
+        questions
+    }
+
+    /// Returns the questions that were answered with `yes` in either set.
+    pub fn union(&self, other: &AnswerSet) -> AnswerSet {
+        self.combine(other, |a, b| a | b)
+    }
+
+    /// Returns the questions that were answered with `yes` in both sets.
+    pub fn intersection(&self, other: &AnswerSet) -> AnswerSet {
+        self.combine(other, |a, b| a & b)
+    }
+
+    /// Returns the questions that were answered with `yes` in this set but not in `other`.
+    pub fn difference(&self, other: &AnswerSet) -> AnswerSet {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    /// Returns the questions that were answered with `yes` in exactly one of the sets.
+    pub fn symmetric_difference(&self, other: &AnswerSet) -> AnswerSet {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    /// Combines two sets word by word, sets of a different width are padded with zeroes.
+    fn combine<F: Fn(u64, u64) -> u64>(&self, other: &AnswerSet, op: F) -> AnswerSet {
+        let len = self.words.len().max(other.words.len());
+        let words = (0..len)
+            .map(|i| {
+                op(
+                    *self.words.get(i).unwrap_or(&0),
+                    *other.words.get(i).unwrap_or(&0),
+                )
+            })
+            .collect();
+        AnswerSet { words }
+    }
+}
+
+/// Maps the characters used in declarations onto question indices.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Alphabet {
+    questions: Vec<char>,
+    indices: HashMap<char, usize>,
+}
+
+impl Alphabet {
+    /// Creates an alphabet from the given questions, duplicates are ignored.
+    pub fn new<I: IntoIterator<Item = char>>(questions: I) -> Self {
+        let mut alphabet = Alphabet {
+            questions: Vec::new(),
+            indices: HashMap::new(),
+        };
+        for question in questions {
+            if !alphabet.indices.contains_key(&question) {
+                alphabet.indices.insert(question, alphabet.questions.len());
+                alphabet.questions.push(question);
+            }
+        }
+        alphabet
+    }
+
+    /// The alphabet used by the puzzle, questions `a` through `z`.
+    pub fn lowercase() -> Self {
+        Alphabet::new('a'..='z')
+    }
+
+    /// Creates an alphabet of every distinct character that appears in `declarations`, ordered
+    /// by first appearance.
+    pub fn from_declarations(declarations: &[Vec<String>]) -> Self {
+        Alphabet::new(
+            declarations
+                .iter()
+                .flat_map(|group| group.iter())
+                .flat_map(|declaration| declaration.chars()),
+        )
+    }
+
+    /// Returns the number of questions in the alphabet.
+    pub fn len(&self) -> usize {
+        self.questions.len()
+    }
+
+    /// Returns whether the alphabet does not contain any questions.
+    pub fn is_empty(&self) -> bool {
+        self.questions.is_empty()
+    }
+
+    /// Returns the index of `question`, if it is part of the alphabet.
+    pub fn index(&self, question: char) -> Option<usize> {
+        self.indices.get(&question).copied()
+    }
+
+    /// Returns the question at `index`.
+    pub fn question(&self, index: usize) -> Option<char> {
+        self.questions.get(index).copied()
+    }
+}
+
+/// An expression over the answers of the members of a group.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SetExpr {
+    // Questions to which anyone in the group answered `yes`.
+    Any,
+    // Questions to which everyone in the group answered `yes`.
+    All,
+    // Questions to which at least `k` members of the group answered `yes`.
+    AtLeast(usize),
+    // Questions to which the n-th member of the group answered `yes`.
+    Member(usize),
+    Union(Box<SetExpr>, Box<SetExpr>),
+    Intersection(Box<SetExpr>, Box<SetExpr>),
+    Difference(Box<SetExpr>, Box<SetExpr>),
+    SymmetricDifference(Box<SetExpr>, Box<SetExpr>),
+}
+
+/// The answers of every member of a single group.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GroupAnswers {
+    width: usize,
+    members: Vec<AnswerSet>,
+}
+
+impl GroupAnswers {
+    /// Creates the answers for a group from its declarations.
+    ///
+    /// Returns an error if a declaration contains a character that is not part of `alphabet`.
+    pub fn from_declarations(
+        group_declarations: &[String],
+        alphabet: &Alphabet,
+    ) -> Result<Self, String> {
+        let mut members = Vec::with_capacity(group_declarations.len());
+        for declaration in group_declarations.iter() {
+            let mut answers = AnswerSet::new(alphabet.len());
+            for c in declaration.chars() {
+                match alphabet.index(c) {
+                    Some(question) => answers.insert(question),
+                    None => return Err(format!("Unknown question '{}' in: {}", c, declaration)),
+                }
+            }
+            members.push(answers);
+        }
+        Ok(GroupAnswers {
+            width: alphabet.len(),
+            members,
+        })
+    }
+
+    /// Returns the number of members in the group.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns whether the group has no members.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Returns the answers of every member of the group.
+    pub fn members(&self) -> &[AnswerSet] {
+        &self.members
+    }
+
+    /// Returns the questions to which anyone in the group answered `yes`.
+    pub fn any(&self) -> AnswerSet {
+        self.members
+            .iter()
+            .fold(AnswerSet::new(self.width), |acc, m| acc.union(m))
+    }
+
+    /// Returns the questions to which everyone in the group answered `yes`, which is none for a
+    /// group without members.
+    pub fn all(&self) -> AnswerSet {
+        if self.members.is_empty() {
+            return AnswerSet::new(self.width);
+        }
+        self.members
+            .iter()
+            .fold(AnswerSet::full(self.width), |acc, m| acc.intersection(m))
+    }
+
+    /// Returns the questions to which at least `k` members of the group answered `yes`.
+    pub fn at_least(&self, k: usize) -> AnswerSet {
+        if k == 0 {
+            return AnswerSet::full(self.width);
+        }
+        if k > self.members.len() {
+            return AnswerSet::new(self.width);
+        }
+        // Track, per question, whether at least 1, 2, ..., k members answered `yes` as a
+        // sequence of bitsets. Every member is then "added" to the counters in bulk.
+        let mut counters = vec![AnswerSet::new(self.width); k];
+        for member in self.members.iter() {
+            for i in (0..k).rev() {
+                let carry = if i == 0 {
+                    member.clone()
+                } else {
+                    counters[i - 1].intersection(member)
+                };
+                counters[i] = counters[i].union(&carry);
+            }
+        }
+        counters.pop().unwrap()
+    }
+
+    /// Evaluates `expression` against the answers of this group.
+    pub fn evaluate(&self, expression: &SetExpr) -> AnswerSet {
+        match expression {
+            SetExpr::Any => self.any(),
+            SetExpr::All => self.all(),
+            SetExpr::AtLeast(k) => self.at_least(*k),
+            SetExpr::Member(n) => match self.members.get(*n) {
+                Some(member) => member.clone(),
+                None => AnswerSet::new(self.width),
+            },
+            SetExpr::Union(a, b) => self.evaluate(a).union(&self.evaluate(b)),
+            SetExpr::Intersection(a, b) => self.evaluate(a).intersection(&self.evaluate(b)),
+            SetExpr::Difference(a, b) => self.evaluate(a).difference(&self.evaluate(b)),
+            SetExpr::SymmetricDifference(a, b) => {
+                self.evaluate(a).symmetric_difference(&self.evaluate(b))
+            }
+        }
+    }
+}
+
+/// Converts all declarations into group answers using an alphabet of all characters that appear
+/// in the declarations.
+pub fn group_answers(declarations: &[Vec<String>]) -> Vec<GroupAnswers> {
+    let alphabet = Alphabet::from_declarations(declarations);
+    declarations
+        .iter()
+        .map(|group| {
+            GroupAnswers::from_declarations(group, &alphabet)
+                .expect("Expected the alphabet to contain every declared question.")
+        })
+        .collect()
+}
+
+/// Sums the number of questions that satisfy `expression` across all groups.
+pub fn count_declarations(groups: &[GroupAnswers], expression: &SetExpr) -> usize {
+    groups.iter().map(|g| g.evaluate(expression).len()).sum()
+}
+
+/// Statistics for a single group of the survey.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GroupStatistics {
+    // The index of the group in the input.
+    pub index: usize,
+    // The number of members in the group.
+    pub members: usize,
+    // The number of questions to which anyone in the group answered `yes`.
+    pub any: usize,
+    // The number of questions to which everyone in the group answered `yes`.
+    pub all: usize,
+}
+
+impl GroupStatistics {
+    /// The fraction of questions answered by anyone in the group that were answered by everyone
+    /// in the group. Groups that didn't answer any question have an agreement of 0.
+    pub fn agreement(&self) -> f64 {
+        if self.any == 0 {
+            return 0.0;
+        }
+        self.all as f64 / self.any as f64
+    }
+}
+
+/// Per-question and per-group statistics for an entire survey.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SurveyStatistics {
+    pub alphabet: Alphabet,
+    // The number of people that answered `yes`, per question.
+    pub yes_counts: Vec<usize>,
+    // The number of people that answered `yes` to both question i and question j.
+    pub co_occurrence: Vec<Vec<usize>>,
+    // Statistics per group, in the order in which the groups appeared in the input.
+    pub groups: Vec<GroupStatistics>,
+}
+
+impl SurveyStatistics {
+    /// Computes the statistics for declarations as returned by `read_declarations`.
+    pub fn from_declarations(declarations: &[Vec<String>]) -> Self {
+        let alphabet = Alphabet::from_declarations(declarations);
+        let width = alphabet.len();
+        let mut yes_counts = vec![0; width];
+        let mut co_occurrence = vec![vec![0; width]; width];
+        let mut groups = Vec::with_capacity(declarations.len());
+
+        for (index, group) in group_answers(declarations).iter().enumerate() {
+            for member in group.members() {
+                let questions = member.questions();
+                for i in questions.iter() {
+                    yes_counts[*i] += 1;
+                    for j in questions.iter() {
+                        co_occurrence[*i][*j] += 1;
+                    }
+                }
+            }
+            groups.push(GroupStatistics {
+                index,
+                members: group.len(),
+                any: group.any().len(),
+                all: group.all().len(),
+            });
+        }
+
+        SurveyStatistics {
+            alphabet,
+            yes_counts,
+            co_occurrence,
+            groups,
+        }
+    }
+
+    /// Returns the groups ordered by agreement, from the highest to the lowest. Ties are broken
+    /// by the size of the group (larger groups first) and then by their index.
+    pub fn rank_by_consensus(&self) -> Vec<&GroupStatistics> {
+        let mut ranked: Vec<&GroupStatistics> = self.groups.iter().collect();
+        ranked.sort_by(|a, b| {
+            b.agreement()
+                .partial_cmp(&a.agreement())
+                .unwrap()
+                .then(b.members.cmp(&a.members))
+                .then(a.index.cmp(&b.index))
+        });
+        ranked
+    }
+
+    /// Writes the number of `yes` answers per question as CSV.
+    pub fn write_questions_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "question,yes_count")?;
+        for (i, count) in self.yes_counts.iter().enumerate() {
+            writeln!(writer, "{},{}", self.question_label(i), count)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the statistics per group as CSV, ordered by consensus.
+    pub fn write_groups_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "rank,group,members,any,all,agreement")?;
+        for (rank, group) in self.rank_by_consensus().iter().enumerate() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{:.4}",
+                rank + 1,
+                group.index,
+                group.members,
+                group.any,
+                group.all,
+                group.agreement()
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the co-occurrence matrix as CSV, with the questions as row and column headers.
+    pub fn write_co_occurrence_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "question")?;
+        for i in 0..self.alphabet.len() {
+            write!(writer, ",{}", self.question_label(i))?;
+        }
+        writeln!(writer)?;
+        for (i, row) in self.co_occurrence.iter().enumerate() {
+            write!(writer, "{}", self.question_label(i))?;
+            for count in row.iter() {
+                write!(writer, ",{}", count)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the label of the question at `index`, quoted if required for CSV output.
+    fn question_label(&self, index: usize) -> String {
+        match self.alphabet.question(index) {
+            Some(c) if c == ',' || c == '"' => {
+                format!("\"{}\"", c.to_string().replace('"', "\"\""))
+            }
+            Some(c) => c.to_string(),
+            None => String::new(),
+        }
+    }
+}
 
 /// Reads customs declarations from `reader`.
 pub fn read_declarations<R: BufRead>(reader: &mut R) -> Vec<Vec<String>> {
@@ -33,40 +477,12 @@ pub fn read_declarations<R: BufRead>(reader: &mut R) -> Vec<Vec<String>> {
 
 /// Counts the number of declarations to which anyone group members said `yes`.
 pub fn count_any_declarations(declarations: &[Vec<String>]) -> i32 {
-    let mut count: i32 = 0;
-    for group_declarations in declarations.iter() {
-        let mut distinct_declarations: HashSet<char> = HashSet::new();
-        // Split group declarations on individual chars and track them in a HashSet.
-        for declaration in group_declarations.iter() {
-            distinct_declarations.extend(declaration.chars())
-        }
-        count += distinct_declarations.len() as i32
-    }
-    count
+    count_declarations(&group_answers(declarations), &SetExpr::Any) as i32
 }
 
 /// Counts the number of declarations to which `all` group members said `yes`.
 pub fn count_all_declarations(declarations: &[Vec<String>]) -> i32 {
-    let mut count: i32 = 0;
-    for group_declarations in declarations.iter() {
-        let mut declaration_map: HashMap<char, i32> = HashMap::new();
-        // Count how often an item was declared per group in an HashMap.
-        for declaration in group_declarations.iter() {
-            for char in declaration.chars() {
-                let stat = declaration_map.entry(char).or_insert(0);
-                *stat += 1;
-            }
-        }
-
-        // Count which items were declared by every single member in the group.
-        let group_size = group_declarations.len() as i32;
-        for (_, value) in declaration_map.iter() {
-            if *value == group_size {
-                count += 1
-            }
-        }
-    }
-    count
+    count_declarations(&group_answers(declarations), &SetExpr::All) as i32
 }
 
 #[cfg(test)]
@@ -107,4 +523,113 @@ b";
         assert_eq!(count_any_declarations(&d), 11);
         assert_eq!(count_all_declarations(&d), 6);
     }
+
+    #[test]
+    fn test_answer_set() {
+        let mut a = AnswerSet::new(100);
+        let mut b = AnswerSet::new(100);
+        a.insert(1);
+        a.insert(70);
+        b.insert(70);
+        b.insert(99);
+        assert!(a.contains(70));
+        assert!(!a.contains(99));
+        assert_eq!(a.union(&b).questions(), vec!(1, 70, 99));
+        assert_eq!(a.intersection(&b).questions(), vec!(70));
+        assert_eq!(a.difference(&b).questions(), vec!(1));
+        assert_eq!(a.symmetric_difference(&b).questions(), vec!(1, 99));
+        assert_eq!(AnswerSet::full(100).len(), 100);
+
+        // Questions beyond the width of the set are ignored.
+        a.insert(1000);
+        assert!(!a.contains(1000));
+        assert_eq!(a.questions(), vec!(1, 70));
+    }
+
+    #[test]
+    fn test_group_answers() {
+        let alphabet = Alphabet::lowercase();
+        let group = GroupAnswers::from_declarations(
+            &["abc", "abd", "ae"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<String>>(),
+            &alphabet,
+        )
+        .unwrap();
+        assert_eq!(group.any().len(), 5);
+        assert_eq!(group.all().questions(), vec!(0));
+        assert_eq!(group.at_least(2).questions(), vec!(0, 1));
+        assert_eq!(group.at_least(4).len(), 0);
+        assert_eq!(group.evaluate(&SetExpr::AtLeast(usize::MAX)).len(), 0);
+
+        // Nobody answered anything in a group without members.
+        let empty = GroupAnswers::from_declarations(&[], &alphabet).unwrap();
+        assert!(empty.any().is_empty());
+        assert!(empty.all().is_empty());
+        assert_eq!(count_all_declarations(&[Vec::new()]), 0);
+
+        // Questions that only the first member answered.
+        let expression = SetExpr::Difference(
+            Box::new(SetExpr::Member(0)),
+            Box::new(SetExpr::Union(
+                Box::new(SetExpr::Member(1)),
+                Box::new(SetExpr::Member(2)),
+            )),
+        );
+        assert_eq!(group.evaluate(&expression).questions(), vec!(2));
+        assert_eq!(
+            group
+                .evaluate(&SetExpr::SymmetricDifference(
+                    Box::new(SetExpr::Any),
+                    Box::new(SetExpr::All)
+                ))
+                .len(),
+            4
+        );
+
+        assert!(GroupAnswers::from_declarations(&["aB".to_string()], &alphabet).is_err());
+    }
+
+    #[test]
+    fn test_survey_statistics() {
+        let declarations: Vec<Vec<String>> = vec![vec!["ab", "ac"], vec!["a", "a"], vec!["b"]]
+            .into_iter()
+            .map(|g| g.into_iter().map(String::from).collect())
+            .collect();
+        let stats = SurveyStatistics::from_declarations(&declarations);
+        assert_eq!(stats.yes_counts, vec!(4, 2, 1));
+        assert_eq!(
+            stats.co_occurrence,
+            vec!(vec!(4, 1, 1), vec!(1, 2, 0), vec!(1, 0, 1))
+        );
+        assert_eq!(stats.groups[0].agreement(), 1.0 / 3.0);
+        let ranking: Vec<usize> = stats.rank_by_consensus().iter().map(|g| g.index).collect();
+        assert_eq!(ranking, vec!(1, 2, 0));
+
+        let mut csv: Vec<u8> = Vec::new();
+        stats.write_questions_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "question,yes_count\na,4\nb,2\nc,1\n"
+        );
+
+        let mut csv: Vec<u8> = Vec::new();
+        stats.write_groups_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "rank,group,members,any,all,agreement
+1,1,2,1,1,1.0000
+2,2,1,1,1,1.0000
+3,0,2,3,1,0.3333
+"
+        );
+
+        let mut csv: Vec<u8> = Vec::new();
+        stats.write_co_occurrence_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "question,a,b,c\na,4,1,1\nb,1,2,0\nc,1,0,1\n"
+        );
+    }
 }
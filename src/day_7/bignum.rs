@@ -0,0 +1,134 @@
+use std::fmt;
+
+/// A small arbitrary-precision unsigned integer, used to count bags when the count does not fit
+/// in an `u128`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BigCount {
+    // Little-endian base 2^32 digits, without trailing zeroes.
+    limbs: Vec<u32>,
+}
+
+impl BigCount {
+    pub fn zero() -> Self {
+        BigCount { limbs: Vec::new() }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Returns the sum of `self` and `other`.
+    pub fn add(&self, other: &BigCount) -> BigCount {
+        let len = self.limbs.len().max(other.limbs.len());
+        let mut limbs = Vec::with_capacity(len + 1);
+        let mut carry = 0u64;
+        for i in 0..len {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64
+                + *other.limbs.get(i).unwrap_or(&0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        BigCount { limbs }
+    }
+
+    /// Returns the product of `self` and `factor`.
+    pub fn mul_small(&self, factor: u32) -> BigCount {
+        if factor == 0 {
+            return BigCount::zero();
+        }
+        let mut limbs = Vec::with_capacity(self.limbs.len() + 1);
+        let mut carry = 0u64;
+        for limb in self.limbs.iter() {
+            let product = *limb as u64 * factor as u64 + carry;
+            limbs.push(product as u32);
+            carry = product >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        BigCount { limbs }
+    }
+
+    /// Converts the count into an `u128`, if it fits.
+    pub fn to_u128(&self) -> Option<u128> {
+        if self.limbs.len() > 4 {
+            return None;
+        }
+        Some(
+            self.limbs
+                .iter()
+                .rev()
+                .fold(0u128, |acc, limb| (acc << 32) | *limb as u128),
+        )
+    }
+
+    /// Divides `self` by `divisor` in place and returns the remainder.
+    fn div_rem_small(&mut self, divisor: u32) -> u32 {
+        let mut remainder = 0u64;
+        for limb in self.limbs.iter_mut().rev() {
+            let value = (remainder << 32) | *limb as u64;
+            *limb = (value / divisor as u64) as u32;
+            remainder = value % divisor as u64;
+        }
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        remainder as u32
+    }
+}
+
+impl From<u128> for BigCount {
+    fn from(mut value: u128) -> Self {
+        let mut limbs = Vec::new();
+        while value > 0 {
+            limbs.push(value as u32);
+            value >>= 32;
+        }
+        BigCount { limbs }
+    }
+}
+
+impl fmt::Display for BigCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Split the number into groups of 9 decimal digits, least significant group first.
+        let mut value = self.clone();
+        let mut groups: Vec<u32> = Vec::new();
+        while !value.is_zero() {
+            groups.push(value.div_rem_small(1_000_000_000));
+        }
+        write!(f, "{}", groups.pop().unwrap())?;
+        for group in groups.iter().rev() {
+            write!(f, "{:09}", group)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_big_count() {
+        let a = BigCount::from(u128::MAX);
+        assert_eq!(a.to_u128(), Some(u128::MAX));
+        assert_eq!(a.to_string(), u128::MAX.to_string());
+
+        let b = a.add(&BigCount::from(1));
+        assert_eq!(b.to_u128(), None);
+        assert_eq!(b.to_string(), "340282366920938463463374607431768211456");
+        assert_eq!(
+            b.mul_small(10).to_string(),
+            "3402823669209384634633746074317682114560"
+        );
+        assert_eq!(b.mul_small(0), BigCount::zero());
+        assert_eq!(BigCount::zero().to_string(), "0");
+    }
+}
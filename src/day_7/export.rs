@@ -0,0 +1,240 @@
+use super::{BagGraph, BagGraphError};
+use std::collections::HashSet;
+
+/// Determines which part of the graph is exported.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExportScope {
+    // Export every rule.
+    All,
+    // Export the given color and every bag that ends up inside it.
+    ReachableFrom(String),
+    // Export the given color and every bag that may eventually contain it.
+    Reaching(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExportOptions {
+    // The part of the graph to export.
+    pub scope: ExportScope,
+    // Whether bags and edges that are part of a cycle should be highlighted.
+    pub highlight_cycles: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            scope: ExportScope::All,
+            highlight_cycles: true,
+        }
+    }
+}
+
+/// The bags and edges to export, with the bags and edges that are part of a cycle.
+struct Selection {
+    nodes: Vec<(usize, bool)>,
+    edges: Vec<(usize, usize, u32, bool)>,
+}
+
+impl Selection {
+    fn new(graph: &BagGraph, options: &ExportOptions) -> Result<Self, BagGraphError> {
+        let mut included: HashSet<usize> = match &options.scope {
+            ExportScope::All => (0..graph.len()).collect(),
+            ExportScope::ReachableFrom(color) => graph.descendants(color)?,
+            ExportScope::Reaching(color) => graph.ancestors(color)?,
+        };
+        match &options.scope {
+            ExportScope::ReachableFrom(color) | ExportScope::Reaching(color) => {
+                included.insert(graph.id(color)?);
+            }
+            ExportScope::All => {}
+        }
+
+        // A bag is part of a cycle if it shares its component with another bag, or contains
+        // itself.
+        let components = graph.strongly_connected_components();
+        let mut component_sizes = vec![0; graph.len()];
+        for component in components.iter() {
+            component_sizes[*component] += 1;
+        }
+        let on_cycle = |from: usize, to: usize| {
+            options.highlight_cycles
+                && components[from] == components[to]
+                && (from == to || component_sizes[components[from]] > 1)
+        };
+
+        let mut nodes: Vec<(usize, bool)> = Vec::new();
+        let mut edges: Vec<(usize, usize, u32, bool)> = Vec::new();
+        for id in (0..graph.len()).filter(|id| included.contains(id)) {
+            let mut cyclic = false;
+            for (child, quantity) in graph.contents(id) {
+                if included.contains(child) {
+                    let cyclic_edge = on_cycle(id, *child);
+                    cyclic |= cyclic_edge;
+                    edges.push((id, *child, *quantity, cyclic_edge));
+                }
+            }
+            nodes.push((id, cyclic));
+        }
+        Ok(Selection { nodes, edges })
+    }
+}
+
+/// Renders the rules as a Graphviz DOT digraph, where every edge is labelled with the quantity.
+pub fn to_dot(graph: &BagGraph, options: &ExportOptions) -> Result<String, BagGraphError> {
+    let selection = Selection::new(graph, options)?;
+    let quote = |id: usize| {
+        let color = graph.color(id).replace('\\', "\\\\").replace('"', "\\\"");
+        format!("\"{}\"", color)
+    };
+
+    let mut dot = String::from("digraph bags {\n");
+    for (id, cyclic) in selection.nodes.iter() {
+        if *cyclic {
+            dot.push_str(&format!("    {} [color=red];\n", quote(*id)));
+        } else {
+            dot.push_str(&format!("    {};\n", quote(*id)));
+        }
+    }
+    for (from, to, quantity, cyclic) in selection.edges.iter() {
+        let style = if *cyclic { ", color=red" } else { "" };
+        dot.push_str(&format!(
+            "    {} -> {} [label=\"{}\"{}];\n",
+            quote(*from),
+            quote(*to),
+            quantity,
+            style
+        ));
+    }
+    dot.push_str("}\n");
+    Ok(dot)
+}
+
+/// Renders the rules as a Mermaid flowchart, where every edge is labelled with the quantity.
+pub fn to_mermaid(graph: &BagGraph, options: &ExportOptions) -> Result<String, BagGraphError> {
+    let selection = Selection::new(graph, options)?;
+
+    let mut mermaid = String::from("flowchart TD\n");
+    for (id, _) in selection.nodes.iter() {
+        mermaid.push_str(&format!(
+            "    n{}[\"{}\"]\n",
+            id,
+            graph.color(*id).replace('"', "#quot;")
+        ));
+    }
+    for (from, to, quantity, _) in selection.edges.iter() {
+        mermaid.push_str(&format!("    n{} -->|{}| n{}\n", from, quantity, to));
+    }
+
+    // Mermaid styles edges by the order in which they were declared.
+    for (index, _) in selection.edges.iter().enumerate().filter(|(_, e)| e.3) {
+        mermaid.push_str(&format!("    linkStyle {} stroke:red\n", index));
+    }
+    let cyclic_nodes: Vec<String> = selection
+        .nodes
+        .iter()
+        .filter(|(_, cyclic)| *cyclic)
+        .map(|(id, _)| format!("n{}", id))
+        .collect();
+    if !cyclic_nodes.is_empty() {
+        mermaid.push_str("    classDef cycle stroke:red\n");
+        mermaid.push_str(&format!("    class {} cycle\n", cyclic_nodes.join(",")));
+    }
+    Ok(mermaid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_7::{Bag, BagRule};
+    use std::collections::HashMap;
+    use std::io::BufReader;
+
+    fn read_graph() -> BagGraph {
+        let rules = "light red bags contain 1 bright white bag, 2 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain 1 muted yellow bag.
+faded blue bags contain no other bags.";
+        let mut reader = BufReader::new(rules.as_bytes());
+        BagGraph::from_rules(&BagRule::from_reader(&mut reader)).unwrap()
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let graph = read_graph();
+        assert_eq!(
+            to_dot(&graph, &ExportOptions::default()).unwrap(),
+            "digraph bags {
+    \"light red\";
+    \"bright white\";
+    \"muted yellow\" [color=red];
+    \"shiny gold\" [color=red];
+    \"faded blue\";
+    \"light red\" -> \"bright white\" [label=\"1\"];
+    \"light red\" -> \"muted yellow\" [label=\"2\"];
+    \"bright white\" -> \"shiny gold\" [label=\"1\"];
+    \"muted yellow\" -> \"shiny gold\" [label=\"2\", color=red];
+    \"muted yellow\" -> \"faded blue\" [label=\"9\"];
+    \"shiny gold\" -> \"muted yellow\" [label=\"1\", color=red];
+}
+"
+        );
+
+        let options = ExportOptions {
+            scope: ExportScope::Reaching("bright white".to_string()),
+            highlight_cycles: false,
+        };
+        assert_eq!(
+            to_dot(&graph, &options).unwrap(),
+            "digraph bags {
+    \"light red\";
+    \"bright white\";
+    \"light red\" -> \"bright white\" [label=\"1\"];
+}
+"
+        );
+
+        // Backslashes and quotes in colors are escaped.
+        let rules = vec![BagRule {
+            bag: Bag {
+                color: r#"back\slash "quoted""#.to_string(),
+            },
+            contains: HashMap::new(),
+        }];
+        let graph = BagGraph::from_rules(&rules).unwrap();
+        assert_eq!(
+            to_dot(&graph, &ExportOptions::default()).unwrap(),
+            "digraph bags {\n    \"back\\\\slash \\\"quoted\\\"\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let graph = read_graph();
+        let options = ExportOptions {
+            scope: ExportScope::ReachableFrom("shiny gold".to_string()),
+            highlight_cycles: true,
+        };
+        assert_eq!(
+            to_mermaid(&graph, &options).unwrap(),
+            "flowchart TD
+    n2[\"muted yellow\"]
+    n3[\"shiny gold\"]
+    n4[\"faded blue\"]
+    n2 -->|2| n3
+    n2 -->|9| n4
+    n3 -->|1| n2
+    linkStyle 0 stroke:red
+    linkStyle 2 stroke:red
+    classDef cycle stroke:red
+    class n2,n3 cycle
+"
+        );
+
+        let options = ExportOptions {
+            scope: ExportScope::ReachableFrom("dull magenta".to_string()),
+            highlight_cycles: true,
+        };
+        assert!(to_mermaid(&graph, &options).is_err());
+    }
+}
@@ -0,0 +1,394 @@
+use super::{Bag, BagRule};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+/// An error that occurred while parsing a bag rule, with the location at which it occurred.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RuleParseError {
+    // A description of the error.
+    pub message: String,
+    // The byte range in the rule to which the error applies.
+    pub span: Range<usize>,
+    // The index of the content clause in which the error occurred, if any.
+    pub clause: Option<usize>,
+}
+
+impl RuleParseError {
+    /// Renders the rule with the offending part of it underlined.
+    pub fn render(&self, rule: &str) -> String {
+        let width = self.span.end.saturating_sub(self.span.start).max(1);
+        format!(
+            "{}\n{}\n{}{}",
+            self,
+            rule,
+            " ".repeat(rule[..self.span.start.min(rule.len())].chars().count()),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.clause {
+            Some(clause) => write!(
+                f,
+                "{} (clause {}, at {}..{})",
+                self.message,
+                clause + 1,
+                self.span.start,
+                self.span.end
+            ),
+            None => write!(
+                f,
+                "{} (at {}..{})",
+                self.message, self.span.start, self.span.end
+            ),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TokenKind {
+    Word(String),
+    Number(u32),
+    Comma,
+    Period,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    // The byte range of the token in the rule.
+    pub span: Range<usize>,
+}
+
+/// Splits a rule into words, numbers, commas and periods.
+pub fn tokenize(rule: &str) -> Result<Vec<Token>, RuleParseError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = rule.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let (kind, end) = match c {
+            _ if c.is_whitespace() => continue,
+            ',' => (TokenKind::Comma, start + 1),
+            '.' => (TokenKind::Period, start + 1),
+            _ => {
+                // Consume the remainder of the word.
+                let mut end = start + c.len_utf8();
+                while let Some((idx, c)) = chars.peek() {
+                    if c.is_whitespace() || *c == ',' || *c == '.' {
+                        break;
+                    }
+                    end = idx + c.len_utf8();
+                    chars.next();
+                }
+                let word = &rule[start..end];
+                if word.chars().all(|c| c.is_ascii_digit()) {
+                    match word.parse::<u32>() {
+                        Ok(n) => (TokenKind::Number(n), end),
+                        Err(e) => {
+                            return Err(RuleParseError {
+                                message: format!("Invalid quantity '{}': {}", word, e),
+                                span: start..end,
+                                clause: None,
+                            })
+                        }
+                    }
+                } else {
+                    (TokenKind::Word(word.to_string()), end)
+                }
+            }
+        };
+        tokens.push(Token {
+            kind,
+            span: start..end,
+        });
+    }
+    Ok(tokens)
+}
+
+/// Parses a single rule of the form:
+///
+/// ```text
+/// rule     := color "bags" "contain" contents "."
+/// contents := "no" "other" "bags" | clause ("," clause)*
+/// clause   := number color ("bag" | "bags")
+/// ```
+pub fn parse_rule(rule: &str) -> Result<BagRule, RuleParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(rule)?,
+        position: 0,
+        end: rule.len(),
+        clause: None,
+    };
+
+    let (color, _) = parser.color()?;
+    parser.keyword("bags")?;
+    parser.keyword("contain")?;
+
+    let mut contains: HashMap<String, u32> = HashMap::new();
+    if parser.peek_word() == Some("no") {
+        parser.keyword("no")?;
+        parser.keyword("other")?;
+        parser.keyword("bags")?;
+    } else {
+        let mut clause = 0;
+        loop {
+            parser.clause = Some(clause);
+            let start = parser.span().start;
+            let quantity = parser.number()?;
+            let (content_color, _) = parser.color()?;
+            parser.bag()?;
+            if contains.contains_key(&content_color) {
+                return Err(RuleParseError {
+                    message: format!("The color '{}' appears more than once", content_color),
+                    span: start..parser.previous_end(),
+                    clause: Some(clause),
+                });
+            }
+            contains.insert(content_color, quantity);
+
+            match parser.peek() {
+                Some(TokenKind::Comma) => {
+                    parser.position += 1;
+                    clause += 1;
+                }
+                _ => break,
+            }
+        }
+        parser.clause = None;
+    }
+
+    parser.period()?;
+    if parser.position < parser.tokens.len() {
+        return Err(parser.error("Unexpected input after the end of the rule"));
+    }
+
+    Ok(BagRule {
+        bag: Bag { color },
+        contains,
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    // The length of the rule, used to report errors at the end of the input.
+    end: usize,
+    // The index of the content clause that is being parsed.
+    clause: Option<usize>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.position).map(|t| &t.kind)
+    }
+
+    fn peek_word(&self) -> Option<&str> {
+        match self.peek() {
+            Some(TokenKind::Word(w)) => Some(w.as_str()),
+            _ => None,
+        }
+    }
+
+    /// The span of the current token, or an empty span at the end of the rule.
+    fn span(&self) -> Range<usize> {
+        match self.tokens.get(self.position) {
+            Some(token) => token.span.clone(),
+            None => self.end..self.end,
+        }
+    }
+
+    fn previous_end(&self) -> usize {
+        self.tokens[self.position - 1].span.end
+    }
+
+    fn error(&self, message: &str) -> RuleParseError {
+        RuleParseError {
+            message: message.to_string(),
+            span: self.span(),
+            clause: self.clause,
+        }
+    }
+
+    /// Parses one or more words up to, but not including, "bag" or "bags".
+    fn color(&mut self) -> Result<(String, Range<usize>), RuleParseError> {
+        let start = self.span().start;
+        let mut words: Vec<String> = Vec::new();
+        while let Some(word) = self.peek_word() {
+            if word == "bag" || word == "bags" {
+                break;
+            }
+            words.push(word.to_string());
+            self.position += 1;
+        }
+        if words.is_empty() {
+            return Err(self.error("Expected a bag color"));
+        }
+        Ok((words.join(" "), start..self.previous_end()))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), RuleParseError> {
+        if self.peek_word() == Some(keyword) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", keyword)))
+        }
+    }
+
+    /// Parses "bag" or "bags".
+    fn bag(&mut self) -> Result<(), RuleParseError> {
+        match self.peek_word() {
+            Some("bag") | Some("bags") => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(self.error("Expected 'bag' or 'bags'")),
+        }
+    }
+
+    fn number(&mut self) -> Result<u32, RuleParseError> {
+        match self.peek() {
+            Some(TokenKind::Number(n)) => {
+                let n = *n;
+                self.position += 1;
+                Ok(n)
+            }
+            _ => Err(self.error("Expected a quantity")),
+        }
+    }
+
+    fn period(&mut self) -> Result<(), RuleParseError> {
+        match self.peek() {
+            Some(TokenKind::Period) => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => Err(self.error("Expected '.' or ','")),
+        }
+    }
+}
+
+impl fmt::Display for BagRule {
+    /// Writes the rule in the same form as it is parsed, the contents are ordered by color.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} bags contain ", self.bag.color)?;
+        if self.contains.is_empty() {
+            return write!(f, "no other bags.");
+        }
+        let mut contents: Vec<(&String, &u32)> = self.contains.iter().collect();
+        contents.sort();
+        for (idx, (color, quantity)) in contents.into_iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+            let noun = if *quantity == 1 { "bag" } else { "bags" };
+            write!(f, "{} {} {}", quantity, color, noun)?;
+        }
+        write!(f, ".")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::XorShift;
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = parse_rule("dark glass bags contain 1 moss bag, 12 bright grass bags.").unwrap();
+        assert_eq!(rule.bag.color, "dark glass");
+        assert_eq!(rule.contains.len(), 2);
+        assert_eq!(rule.contains["moss"], 1);
+        assert_eq!(rule.contains["bright grass"], 12);
+        assert_eq!(
+            rule.to_string(),
+            "dark glass bags contain 12 bright grass bags, 1 moss bag."
+        );
+
+        let rule = parse_rule("faded blue bags contain no other bags.").unwrap();
+        assert!(rule.contains.is_empty());
+        assert_eq!(rule.to_string(), "faded blue bags contain no other bags.");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let rule = "light red bags contain 1 bright white bag, muted yellow bags.";
+        let error = parse_rule(rule).unwrap_err();
+        assert_eq!(error.message, "Expected a quantity");
+        assert_eq!(error.clause, Some(1));
+        assert_eq!(&rule[error.span.clone()], "muted");
+        assert_eq!(
+            error.render(rule),
+            "Expected a quantity (clause 2, at 43..48)
+light red bags contain 1 bright white bag, muted yellow bags.
+                                           ^^^^^"
+        );
+
+        let rule = "light red bags contain 1 bright white bag, 2 muted yellow.";
+        let error = parse_rule(rule).unwrap_err();
+        assert_eq!(error.message, "Expected 'bag' or 'bags'");
+        assert_eq!(error.clause, Some(1));
+        assert_eq!(&rule[error.span.clone()], ".");
+
+        let rule = "light red bags contain 1 white bag, 2 white bags.";
+        let error = parse_rule(rule).unwrap_err();
+        assert_eq!(error.clause, Some(1));
+        assert_eq!(&rule[error.span.clone()], "2 white bags");
+
+        let error = parse_rule("light red bags hold 1 white bag.").unwrap_err();
+        assert_eq!(error.message, "Expected 'contain'");
+        assert_eq!(error.clause, None);
+
+        let error = parse_rule("light red bags contain 1 white bag").unwrap_err();
+        assert_eq!(error.span, 34..34);
+
+        let error = parse_rule("light red bags contain no other bags. Extra").unwrap_err();
+        assert_eq!(error.message, "Unexpected input after the end of the rule");
+
+        let error = parse_rule("light red bags contain 99999999999 white bags.").unwrap_err();
+        assert_eq!(&error.message[..16], "Invalid quantity");
+    }
+
+    /// Generates a color, which may not contain the words "bag" or "bags", and which may not
+    /// start with "no".
+    fn random_color(random: &mut XorShift) -> String {
+        let words = [
+            "shiny", "gold", "dark", "glass", "moss", "bright", "grass", "plus", "dress", "bag",
+            "bags", "contain", "other", "no",
+        ];
+        let mut parts: Vec<&str> = Vec::new();
+        for _ in 0..=random.between(0, 2) {
+            let word = words[random.between(0, words.len() as u64 - 1) as usize];
+            if word == "bag" || word == "bags" || (parts.is_empty() && word == "no") {
+                continue;
+            }
+            parts.push(word);
+        }
+        if parts.is_empty() {
+            parts.push("plain");
+        }
+        parts.join(" ")
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut random = XorShift::new(0x2020_1207);
+        for _ in 0..1000 {
+            let mut contains = HashMap::new();
+            let bag = Bag {
+                color: random_color(&mut random),
+            };
+            for _ in 0..random.between(0, 4) {
+                contains.insert(random_color(&mut random), random.between(1, 20) as u32);
+            }
+            let rule = BagRule { bag, contains };
+
+            let text = rule.to_string();
+            let parsed = parse_rule(&text).unwrap_or_else(|e| panic!("{}", e.render(&text)));
+            assert_eq!(parsed, rule);
+            assert_eq!(parsed.to_string(), text);
+        }
+    }
+}
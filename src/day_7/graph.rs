@@ -0,0 +1,730 @@
+use super::{BagRule, BigCount};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// Errors that may occur while building or querying a `BagGraph`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BagGraphError {
+    // A rule references a color for which there is no rule.
+    MissingRule {
+        color: String,
+        referenced_by: String,
+    },
+    // There is more than one rule for the same color.
+    DuplicateRule {
+        color: String,
+    },
+    // A query referenced a color that is not part of the graph.
+    UnknownColor {
+        color: String,
+    },
+    // The rules contain a cycle, the path starts and ends with the same color.
+    Cycle {
+        path: Vec<String>,
+    },
+    // The number of bags inside a bag of `color` does not fit in an `u128`.
+    Overflow {
+        color: String,
+    },
+}
+
+impl fmt::Display for BagGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BagGraphError::MissingRule {
+                color,
+                referenced_by,
+            } => write!(
+                f,
+                "There is no rule for '{}', which is referenced by '{}'.",
+                color, referenced_by
+            ),
+            BagGraphError::DuplicateRule { color } => {
+                write!(f, "There is more than one rule for '{}'.", color)
+            }
+            BagGraphError::UnknownColor { color } => write!(f, "Unknown bag color '{}'.", color),
+            BagGraphError::Cycle { path } => {
+                write!(f, "The rules contain a cycle: {}.", path.join(" -> "))
+            }
+            BagGraphError::Overflow { color } => write!(
+                f,
+                "The number of bags inside a '{}' bag does not fit in an u128.",
+                color
+            ),
+        }
+    }
+}
+
+/// A bag that may eventually contain a particular target bag.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ancestor {
+    // The color of the bag.
+    pub color: String,
+    // The shortest chain of colors from this bag down to the target, including both.
+    pub path: Vec<String>,
+    // The smallest number of containment levels between this bag and the target.
+    pub min_depth: usize,
+    // The largest number of containment levels between this bag and the target.
+    pub max_depth: usize,
+}
+
+/// A directed graph of bag rules, where every color is interned into a numeric id.
+///
+/// An edge from `a` to `b` with quantity `n` means that a bag of color `a` must contain `n` bags
+/// of color `b`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BagGraph {
+    // The color for every id.
+    colors: Vec<String>,
+    // The id for every color.
+    ids: HashMap<String, usize>,
+    // The bags that must be contained by the bag with a particular id, with their quantities.
+    contents: Vec<Vec<(usize, u32)>>,
+    // The bags that must contain the bag with a particular id, with their quantities.
+    containers: Vec<Vec<(usize, u32)>>,
+}
+
+impl BagGraph {
+    /// Builds a graph from the given rules.
+    ///
+    /// Every color that is referenced by a rule must have a rule of its own.
+    pub fn from_rules(rules: &[BagRule]) -> Result<Self, BagGraphError> {
+        let mut colors: Vec<String> = Vec::with_capacity(rules.len());
+        let mut ids: HashMap<String, usize> = HashMap::with_capacity(rules.len());
+        for rule in rules.iter() {
+            if ids.contains_key(&rule.bag.color) {
+                return Err(BagGraphError::DuplicateRule {
+                    color: rule.bag.color.clone(),
+                });
+            }
+            ids.insert(rule.bag.color.clone(), colors.len());
+            colors.push(rule.bag.color.clone());
+        }
+
+        let mut contents: Vec<Vec<(usize, u32)>> = vec![Vec::new(); colors.len()];
+        let mut containers: Vec<Vec<(usize, u32)>> = vec![Vec::new(); colors.len()];
+        for (id, rule) in rules.iter().enumerate() {
+            // Visit the contents in sorted order, such that a missing rule is reported
+            // deterministically.
+            let mut rule_contents: Vec<(&String, &u32)> = rule.contains.iter().collect();
+            rule_contents.sort_unstable();
+            for (color, quantity) in rule_contents {
+                let child = match ids.get(color) {
+                    Some(child) => *child,
+                    None => {
+                        return Err(BagGraphError::MissingRule {
+                            color: color.clone(),
+                            referenced_by: rule.bag.color.clone(),
+                        })
+                    }
+                };
+                contents[id].push((child, *quantity));
+                containers[child].push((id, *quantity));
+            }
+        }
+
+        // Keep the adjacency lists in a deterministic order.
+        for edges in contents.iter_mut().chain(containers.iter_mut()) {
+            edges.sort_unstable();
+        }
+
+        Ok(BagGraph {
+            colors,
+            ids,
+            contents,
+            containers,
+        })
+    }
+
+    /// Returns the number of colors in the graph.
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Returns whether the graph does not contain any colors.
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Returns the id of `color`.
+    pub fn id(&self, color: &str) -> Result<usize, BagGraphError> {
+        self.ids
+            .get(color)
+            .copied()
+            .ok_or_else(|| BagGraphError::UnknownColor {
+                color: color.to_string(),
+            })
+    }
+
+    /// Returns the color of the bag with the given `id`.
+    pub fn color(&self, id: usize) -> &str {
+        &self.colors[id]
+    }
+
+    /// Returns all colors in the graph, indexed by their id.
+    pub fn colors(&self) -> &[String] {
+        &self.colors
+    }
+
+    /// Returns the ids and quantities of the bags that a bag with `id` must contain.
+    pub fn contents(&self, id: usize) -> &[(usize, u32)] {
+        &self.contents[id]
+    }
+
+    /// Returns the ids and quantities of the bags that must directly contain a bag with `id`.
+    pub fn containers(&self, id: usize) -> &[(usize, u32)] {
+        &self.containers[id]
+    }
+
+    /// Finds a cycle anywhere in the graph, and returns the colors along it. The first and last
+    /// color of the path are the same.
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        let mut state = vec![VisitState::New; self.len()];
+        let mut post_order = Vec::new();
+        (0..self.len())
+            .find_map(|start| self.depth_first(start, &self.contents, &mut state, &mut post_order))
+    }
+
+    /// Returns an error describing the cycle if a cycle can be reached from `id`.
+    pub fn check_acyclic_from(&self, id: usize) -> Result<(), BagGraphError> {
+        self.post_order_from(id).map(|_| ())
+    }
+
+    /// Returns the ids of all colors ordered such that every bag appears before the bags it must
+    /// contain.
+    pub fn topological_order(&self) -> Result<Vec<usize>, BagGraphError> {
+        // Kahn's algorithm, using the number of containers as the in-degree.
+        let mut in_degree: Vec<usize> = self.containers.iter().map(|c| c.len()).collect();
+        let mut queue: VecDeque<usize> = (0..self.len()).filter(|id| in_degree[*id] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            for (child, _) in self.contents(id) {
+                in_degree[*child] -= 1;
+                if in_degree[*child] == 0 {
+                    queue.push_back(*child);
+                }
+            }
+        }
+        if order.len() < self.len() {
+            // Some colors are part of, or contained by, a cycle.
+            return Err(BagGraphError::Cycle {
+                path: self.find_cycle().unwrap_or_default(),
+            });
+        }
+        Ok(order)
+    }
+
+    /// Returns ids of all bags that are contained by the bag with `id` (including `id` itself),
+    /// ordered such that every bag appears after the bags it must contain.
+    fn post_order_from(&self, id: usize) -> Result<Vec<usize>, BagGraphError> {
+        let mut state = vec![VisitState::New; self.len()];
+        let mut post_order = Vec::new();
+        match self.depth_first(id, &self.contents, &mut state, &mut post_order) {
+            Some(path) => Err(BagGraphError::Cycle { path }),
+            None => Ok(post_order),
+        }
+    }
+
+    /// Returns the ids of all bags that may eventually contain a bag of `color`.
+    pub fn ancestors(&self, color: &str) -> Result<HashSet<usize>, BagGraphError> {
+        let target = self.id(color)?;
+        Ok(self.reachable(target, &self.containers))
+    }
+
+    /// Returns the ids of all bags that eventually end up inside a bag of `color`.
+    pub fn descendants(&self, color: &str) -> Result<HashSet<usize>, BagGraphError> {
+        let target = self.id(color)?;
+        Ok(self.reachable(target, &self.contents))
+    }
+
+    /// Assigns every bag to a strongly connected component and returns the component of every
+    /// id. Two bags share a component only if they are part of the same cycle.
+    pub fn strongly_connected_components(&self) -> Vec<usize> {
+        // Kosaraju's algorithm: order the bags by the time at which their depth-first search
+        // finished, then collect the components along the reversed edges in reverse order.
+        let mut visited = vec![false; self.len()];
+        let mut finished: Vec<usize> = Vec::with_capacity(self.len());
+        for start in 0..self.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut stack: Vec<(usize, usize)> = vec![(start, 0)];
+            while let Some((id, edge)) = stack.last_mut() {
+                let id = *id;
+                match self.contents[id].get(*edge) {
+                    Some((child, _)) => {
+                        *edge += 1;
+                        if !visited[*child] {
+                            visited[*child] = true;
+                            stack.push((*child, 0));
+                        }
+                    }
+                    None => {
+                        finished.push(id);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        let mut components: Vec<Option<usize>> = vec![None; self.len()];
+        let mut num_components = 0;
+        for start in finished.into_iter().rev() {
+            if components[start].is_some() {
+                continue;
+            }
+            components[start] = Some(num_components);
+            let mut stack = vec![start];
+            while let Some(id) = stack.pop() {
+                for (container, _) in self.containers(id) {
+                    if components[*container].is_none() {
+                        components[*container] = Some(num_components);
+                        stack.push(*container);
+                    }
+                }
+            }
+            num_components += 1;
+        }
+        components.into_iter().map(|c| c.unwrap()).collect()
+    }
+
+    /// Returns the ids of all bags that can be reached from `start` along `edges`, excluding the
+    /// `start` bag itself.
+    fn reachable(&self, start: usize, edges: &[Vec<(usize, u32)>]) -> HashSet<usize> {
+        let mut found: HashSet<usize> = HashSet::new();
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        queue.push_back(start);
+        while let Some(id) = queue.pop_front() {
+            for (next, _) in edges[id].iter() {
+                if found.insert(*next) {
+                    queue.push_back(*next);
+                }
+            }
+        }
+        // A bag can only reach itself if the rules are cyclic.
+        found.remove(&start);
+        found
+    }
+
+    /// Returns every bag that may eventually contain a bag of `color`, with its shortest
+    /// containment chain and its minimum and maximum nesting depth, ordered by minimum depth.
+    ///
+    /// Returns an error if any of these bags is part of a cycle, as the maximum depth would be
+    /// unbounded.
+    pub fn ancestor_paths(&self, color: &str) -> Result<Vec<Ancestor>, BagGraphError> {
+        let target = self.id(color)?;
+
+        // Order the ancestors such that every bag is visited after all of the bags it contains
+        // on the way to the target, while following the edges from the target outwards.
+        let mut state = vec![VisitState::New; self.len()];
+        let mut post_order = Vec::new();
+        if let Some(mut path) =
+            self.depth_first(target, &self.containers, &mut state, &mut post_order)
+        {
+            // The path was found by following the containers, report it outside-in.
+            path.reverse();
+            return Err(BagGraphError::Cycle { path });
+        }
+
+        let mut max_depth: Vec<usize> = vec![0; self.len()];
+        for node in post_order.iter().rev() {
+            for (container, _) in self.containers(*node) {
+                max_depth[*container] = max_depth[*container].max(max_depth[*node] + 1);
+            }
+        }
+
+        // A breadth-first search yields the shortest chains.
+        let mut min_depth: Vec<Option<usize>> = vec![None; self.len()];
+        let mut next: Vec<usize> = vec![target; self.len()];
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        min_depth[target] = Some(0);
+        queue.push_back(target);
+        while let Some(node) = queue.pop_front() {
+            for (container, _) in self.containers(node) {
+                if min_depth[*container].is_none() {
+                    min_depth[*container] = min_depth[node].map(|d| d + 1);
+                    next[*container] = node;
+                    queue.push_back(*container);
+                }
+            }
+        }
+
+        let mut ancestors: Vec<Ancestor> = Vec::new();
+        for node in post_order.into_iter().filter(|n| *n != target) {
+            let mut path = vec![self.colors[node].clone()];
+            let mut current = node;
+            while current != target {
+                current = next[current];
+                path.push(self.colors[current].clone());
+            }
+            ancestors.push(Ancestor {
+                color: self.colors[node].clone(),
+                path,
+                min_depth: min_depth[node].unwrap(),
+                max_depth: max_depth[node],
+            });
+        }
+        ancestors.sort_by(|a, b| {
+            a.min_depth
+                .cmp(&b.min_depth)
+                .then_with(|| a.color.cmp(&b.color))
+        });
+        Ok(ancestors)
+    }
+
+    /// Counts how many bags need to be contained in a bag of `color`.
+    ///
+    /// Every bag that is reachable from `color` is only counted once, after the bags it contains.
+    pub fn count_bags(&self, color: &str) -> Result<u128, BagGraphError> {
+        let id = self.id(color)?;
+        let mut counts: Vec<u128> = vec![0; self.len()];
+        for node in self.post_order_from(id)? {
+            counts[node] = self.count_contents(node, &counts)?;
+        }
+        Ok(counts[id])
+    }
+
+    /// Counts how many bags need to be contained in a bag of every color, indexed by id.
+    pub fn count_all_bags(&self) -> Result<Vec<u128>, BagGraphError> {
+        let mut counts: Vec<u128> = vec![0; self.len()];
+        for node in self.topological_order()?.into_iter().rev() {
+            counts[node] = self.count_contents(node, &counts)?;
+        }
+        Ok(counts)
+    }
+
+    /// Counts how many bags need to be contained in a bag of `color` without the risk of
+    /// overflowing.
+    pub fn count_bags_big(&self, color: &str) -> Result<BigCount, BagGraphError> {
+        let id = self.id(color)?;
+        let mut counts: Vec<BigCount> = vec![BigCount::zero(); self.len()];
+        for node in self.post_order_from(id)? {
+            let mut count = BigCount::zero();
+            for (child, quantity) in self.contents(node) {
+                // Every child bag, plus everything it contains.
+                count = count.add(&counts[*child].add(&BigCount::from(1)).mul_small(*quantity));
+            }
+            counts[node] = count;
+        }
+        Ok(counts[id].clone())
+    }
+
+    /// Returns how many bags of each color end up inside a bag of `color`, keyed on color.
+    pub fn contents_breakdown(&self, color: &str) -> Result<BTreeMap<String, u128>, BagGraphError> {
+        let id = self.id(color)?;
+        // Propagate the number of copies of every bag from the outer bag inwards, every bag is
+        // visited after all of the bags that contain it.
+        let mut copies: Vec<u128> = vec![0; self.len()];
+        copies[id] = 1;
+        let mut breakdown: BTreeMap<String, u128> = BTreeMap::new();
+        for node in self.post_order_from(id)?.into_iter().rev() {
+            for (child, quantity) in self.contents(node) {
+                copies[*child] = copies[node]
+                    .checked_mul(*quantity as u128)
+                    .and_then(|n| n.checked_add(copies[*child]))
+                    .ok_or_else(|| BagGraphError::Overflow {
+                        color: color.to_string(),
+                    })?;
+            }
+            if node != id {
+                breakdown.insert(self.colors[node].clone(), copies[node]);
+            }
+        }
+        Ok(breakdown)
+    }
+
+    /// Counts the bags inside the bag with `id`, given the counts of all bags it contains.
+    fn count_contents(&self, id: usize, counts: &[u128]) -> Result<u128, BagGraphError> {
+        let mut count: u128 = 0;
+        for (child, quantity) in self.contents(id) {
+            count = counts[*child]
+                .checked_add(1)
+                .and_then(|n| n.checked_mul(*quantity as u128))
+                .and_then(|n| n.checked_add(count))
+                .ok_or_else(|| BagGraphError::Overflow {
+                    color: self.colors[id].clone(),
+                })?;
+        }
+        Ok(count)
+    }
+
+    /// Performs an iterative depth-first search from `start` along `edges`, which is either the
+    /// contents or the containers of every bag, and returns the first cycle found.
+    ///
+    /// Nodes that were fully explored by an earlier search are marked as `Done` in `state`, such
+    /// that they are not explored again. Every node is pushed onto `post_order` once all of the
+    /// nodes it has edges to have been explored.
+    fn depth_first(
+        &self,
+        start: usize,
+        edges: &[Vec<(usize, u32)>],
+        state: &mut [VisitState],
+        post_order: &mut Vec<usize>,
+    ) -> Option<Vec<String>> {
+        if state[start] == VisitState::Done {
+            return None;
+        }
+        // The current path, with the index of the next edge to explore for every node.
+        let mut stack: Vec<(usize, usize)> = vec![(start, 0)];
+        state[start] = VisitState::OnPath;
+        while let Some((id, edge)) = stack.last_mut() {
+            let id = *id;
+            match edges[id].get(*edge) {
+                Some((child, _)) => {
+                    *edge += 1;
+                    match state[*child] {
+                        VisitState::New => {
+                            state[*child] = VisitState::OnPath;
+                            stack.push((*child, 0));
+                        }
+                        VisitState::OnPath => {
+                            // Found a cycle, the path runs from `child` to the top of the stack.
+                            let offset = stack.iter().position(|(n, _)| n == child).unwrap();
+                            let mut path: Vec<String> = stack[offset..]
+                                .iter()
+                                .map(|(n, _)| self.colors[*n].clone())
+                                .collect();
+                            path.push(self.colors[*child].clone());
+                            return Some(path);
+                        }
+                        VisitState::Done => {}
+                    }
+                }
+                None => {
+                    state[id] = VisitState::Done;
+                    post_order.push(id);
+                    stack.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum VisitState {
+    New,
+    OnPath,
+    Done,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn read_graph(rules: &str) -> Result<BagGraph, BagGraphError> {
+        let mut reader = BufReader::new(rules.as_bytes());
+        BagGraph::from_rules(&BagRule::from_reader(&mut reader))
+    }
+
+    #[test]
+    fn test_from_rules() {
+        let graph = read_graph(
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+shiny gold bags contain no other bags.
+faded blue bags contain no other bags.",
+        )
+        .unwrap();
+        assert_eq!(graph.len(), 5);
+        let gold = graph.id("shiny gold").unwrap();
+        assert_eq!(graph.color(gold), "shiny gold");
+        assert_eq!(graph.containers(gold), &[(1, 1), (2, 2)]);
+        assert_eq!(graph.contents(0), &[(1, 1), (2, 2)]);
+        assert_eq!(graph.ancestors("shiny gold").unwrap().len(), 3);
+        assert_eq!(
+            graph.count_bags("light red").unwrap(),
+            1 + 1 + 2 + 2 * (2 + 9)
+        );
+        assert_eq!(graph.find_cycle(), None);
+        assert_eq!(
+            graph.count_bags("dull magenta"),
+            Err(BagGraphError::UnknownColor {
+                color: "dull magenta".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_missing_rule() {
+        assert_eq!(
+            read_graph("light red bags contain 1 bright white bag."),
+            Err(BagGraphError::MissingRule {
+                color: "bright white".to_string(),
+                referenced_by: "light red".to_string()
+            })
+        );
+        // The first missing color in sorted order is reported.
+        for _ in 0..10 {
+            assert_eq!(
+                read_graph("light red bags contain 1 muted yellow bag, 2 dark orange bags, 1 bright white bag."),
+                Err(BagGraphError::MissingRule {
+                    color: "bright white".to_string(),
+                    referenced_by: "light red".to_string()
+                })
+            );
+        }
+        assert_eq!(
+            read_graph(
+                "light red bags contain no other bags.
+light red bags contain no other bags."
+            ),
+            Err(BagGraphError::DuplicateRule {
+                color: "light red".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let graph = read_graph(
+            "light red bags contain 1 bright white bag.
+bright white bags contain 1 shiny gold bag, 2 faded blue bags.
+shiny gold bags contain 1 light red bag.
+faded blue bags contain no other bags.",
+        )
+        .unwrap();
+        let cycle = vec!["light red", "bright white", "shiny gold", "light red"]
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<String>>();
+        assert_eq!(graph.find_cycle(), Some(cycle.clone()));
+        assert_eq!(
+            graph.count_bags("light red"),
+            Err(BagGraphError::Cycle { path: cycle })
+        );
+        assert_eq!(graph.count_bags("faded blue"), Ok(0));
+        assert_eq!(graph.ancestors("light red").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_count_bags() {
+        let graph = read_graph(
+            "shiny gold bags contain 2 dark red bags, 1 dark blue bag.
+dark red bags contain 2 dark orange bags, 3 dark blue bags.
+dark orange bags contain 1 dark blue bag.
+dark blue bags contain no other bags.",
+        )
+        .unwrap();
+        assert_eq!(graph.count_bags("shiny gold"), Ok(2 + 2 * (2 + 2 + 3) + 1));
+        assert_eq!(
+            graph.count_bags_big("shiny gold").unwrap().to_string(),
+            "17"
+        );
+        assert_eq!(graph.count_all_bags(), Ok(vec!(17, 7, 1, 0)));
+        assert_eq!(graph.topological_order(), Ok(vec!(0, 1, 2, 3)));
+
+        let breakdown = graph.contents_breakdown("shiny gold").unwrap();
+        assert_eq!(breakdown.len(), 3);
+        assert_eq!(breakdown["dark red"], 2);
+        assert_eq!(breakdown["dark orange"], 4);
+        assert_eq!(breakdown["dark blue"], 1 + 2 * 3 + 4);
+        assert_eq!(breakdown.values().sum::<u128>(), 17);
+    }
+
+    #[test]
+    fn test_count_bags_overflow() {
+        // Every bag contains two bags of the next color, 200 levels deep.
+        let mut rules = String::new();
+        for level in 0..200 {
+            rules.push_str(&format!(
+                "level{} bags contain 2 level{} bags.\n",
+                level,
+                level + 1
+            ));
+        }
+        rules.push_str("level200 bags contain no other bags.");
+        let graph = read_graph(&rules).unwrap();
+
+        assert_eq!(
+            graph.count_bags("level0"),
+            Err(BagGraphError::Overflow {
+                color: "level72".to_string()
+            })
+        );
+        assert_eq!(graph.count_bags("level73"), Ok(u128::MAX - 1));
+        assert_eq!(
+            graph.count_bags_big("level0").unwrap().to_string(),
+            "3213876088517980551083924184682325205044405987565585670602750"
+        );
+    }
+
+    #[test]
+    fn test_ancestor_paths() {
+        let graph = read_graph(
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.
+dark orange bags contain 3 bright white bags, 4 muted yellow bags.
+bright white bags contain 1 shiny gold bag.
+muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
+wavy purple bags contain 1 light red bag, 2 shiny gold bags.
+shiny gold bags contain 1 dark olive bag.
+dark olive bags contain no other bags.
+faded blue bags contain no other bags.",
+        )
+        .unwrap();
+        let ancestors = graph.ancestor_paths("shiny gold").unwrap();
+        let colors: Vec<&str> = ancestors.iter().map(|a| a.color.as_str()).collect();
+        assert_eq!(
+            colors,
+            vec!(
+                "bright white",
+                "muted yellow",
+                "wavy purple",
+                "dark orange",
+                "light red"
+            )
+        );
+        let purple = &ancestors[2];
+        assert_eq!(purple.path, vec!("wavy purple", "shiny gold"));
+        assert_eq!((purple.min_depth, purple.max_depth), (1, 3));
+        let red = &ancestors[4];
+        assert_eq!(red.path, vec!("light red", "bright white", "shiny gold"));
+        assert_eq!((red.min_depth, red.max_depth), (2, 2));
+
+        assert_eq!(graph.ancestor_paths("wavy purple"), Ok(vec!()));
+        assert_eq!(
+            graph.ancestor_paths("dark olive").unwrap().len(),
+            graph.ancestors("dark olive").unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_ancestor_paths_cycle() {
+        let graph = read_graph(
+            "light red bags contain 1 bright white bag.
+bright white bags contain 1 shiny gold bag, 1 light red bag.
+shiny gold bags contain no other bags.",
+        )
+        .unwrap();
+        assert_eq!(
+            graph.ancestor_paths("shiny gold"),
+            Err(BagGraphError::Cycle {
+                path: vec!("bright white", "light red", "bright white")
+                    .into_iter()
+                    .map(String::from)
+                    .collect()
+            })
+        );
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let graph = read_graph(
+            "light red bags contain 1 bright white bag.
+bright white bags contain 1 shiny gold bag, 2 faded blue bags.
+shiny gold bags contain 1 light red bag.
+faded blue bags contain no other bags.",
+        )
+        .unwrap();
+        let components = graph.strongly_connected_components();
+        assert_eq!(components[0], components[1]);
+        assert_eq!(components[0], components[2]);
+        assert_ne!(components[0], components[3]);
+        assert_eq!(graph.descendants("shiny gold").unwrap().len(), 3);
+        assert_eq!(graph.descendants("faded blue").unwrap().len(), 0);
+    }
+}
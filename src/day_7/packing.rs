@@ -0,0 +1,172 @@
+use super::{BagGraph, BagGraphError};
+use std::collections::{BTreeMap, HashMap};
+
+/// Returns the loose bags, by color, that are needed to assemble a single bag of `color`. This
+/// includes the outer bag itself.
+pub fn requirements(
+    graph: &BagGraph,
+    color: &str,
+) -> Result<BTreeMap<String, u128>, BagGraphError> {
+    let mut required = graph.contents_breakdown(color)?;
+    required.insert(color.to_string(), 1);
+    Ok(required)
+}
+
+/// Returns how many bags of `color` can be fully assembled from the loose bags in `inventory`.
+pub fn max_assemblable(
+    graph: &BagGraph,
+    inventory: &HashMap<String, u128>,
+    color: &str,
+) -> Result<u128, BagGraphError> {
+    let required = requirements(graph, color)?;
+    // Bags that are required zero times, e.g. by a rule such as "contain 0 bright white bags",
+    // don't limit the result.
+    Ok(required
+        .iter()
+        .filter(|(_, n)| **n > 0)
+        .map(|(c, n)| inventory.get(c).unwrap_or(&0) / n)
+        .min()
+        .unwrap_or(0))
+}
+
+/// Returns every color of which at least one bag can be fully assembled from the loose bags in
+/// `inventory`, together with the number of bags that can be assembled, ordered by color.
+///
+/// Every color is considered on its own, i.e. assembling one color uses up bags that might also
+/// be needed to assemble another. Colors for which the requirements can't be determined, e.g.
+/// because they are part of a cycle, are reported with their error.
+pub fn assemblable(
+    graph: &BagGraph,
+    inventory: &HashMap<String, u128>,
+) -> Vec<(String, Result<u128, BagGraphError>)> {
+    let mut result: Vec<(String, Result<u128, BagGraphError>)> = Vec::new();
+    for color in graph.colors().iter() {
+        // Bags can only be assembled if the outer bag is part of the inventory.
+        if inventory.get(color).unwrap_or(&0) == &0 {
+            continue;
+        }
+        match max_assemblable(graph, inventory, color) {
+            Ok(0) => {}
+            count => result.push((color.clone(), count)),
+        }
+    }
+    result.sort_by(|a, b| a.0.cmp(&b.0));
+    result
+}
+
+/// Returns the loose bags, by color, that are missing from `inventory` in order to assemble
+/// `quantity` bags of `color`. The result is empty if the bags can be assembled.
+pub fn shortfall(
+    graph: &BagGraph,
+    inventory: &HashMap<String, u128>,
+    color: &str,
+    quantity: u128,
+) -> Result<BTreeMap<String, u128>, BagGraphError> {
+    let mut missing: BTreeMap<String, u128> = BTreeMap::new();
+    for (c, n) in requirements(graph, color)? {
+        let needed = n
+            .checked_mul(quantity)
+            .ok_or_else(|| BagGraphError::Overflow {
+                color: color.to_string(),
+            })?;
+        let available = *inventory.get(&c).unwrap_or(&0);
+        if needed > available {
+            missing.insert(c, needed - available);
+        }
+    }
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_7::BagRule;
+    use std::io::BufReader;
+
+    fn read_graph() -> BagGraph {
+        let rules = "shiny gold bags contain 2 dark red bags, 1 dark blue bag.
+dark red bags contain 3 dark blue bags.
+dark blue bags contain no other bags.";
+        let mut reader = BufReader::new(rules.as_bytes());
+        BagGraph::from_rules(&BagRule::from_reader(&mut reader)).unwrap()
+    }
+
+    fn inventory(bags: &[(&str, u128)]) -> HashMap<String, u128> {
+        bags.iter().map(|(c, n)| (c.to_string(), *n)).collect()
+    }
+
+    #[test]
+    fn test_requirements() {
+        let graph = read_graph();
+        let required = requirements(&graph, "shiny gold").unwrap();
+        assert_eq!(
+            required.into_iter().collect::<Vec<(String, u128)>>(),
+            vec!(
+                ("dark blue".to_string(), 7),
+                ("dark red".to_string(), 2),
+                ("shiny gold".to_string(), 1)
+            )
+        );
+    }
+
+    #[test]
+    fn test_assemblable() {
+        let graph = read_graph();
+        let inventory = inventory(&[("shiny gold", 5), ("dark red", 5), ("dark blue", 15)]);
+        assert_eq!(max_assemblable(&graph, &inventory, "shiny gold"), Ok(2));
+        assert_eq!(max_assemblable(&graph, &inventory, "dark red"), Ok(5));
+        assert_eq!(
+            assemblable(&graph, &inventory),
+            vec!(
+                ("dark blue".to_string(), Ok(15)),
+                ("dark red".to_string(), Ok(5)),
+                ("shiny gold".to_string(), Ok(2))
+            )
+        );
+        assert!(max_assemblable(&graph, &inventory, "dull magenta").is_err());
+    }
+
+    #[test]
+    fn test_assemblable_zero_quantity() {
+        let rules = "light red bags contain 0 bright white bags.
+bright white bags contain no other bags.";
+        let mut reader = BufReader::new(rules.as_bytes());
+        let graph = BagGraph::from_rules(&BagRule::from_reader(&mut reader)).unwrap();
+        let inventory = inventory(&[("light red", 3)]);
+        assert_eq!(max_assemblable(&graph, &inventory, "light red"), Ok(3));
+        assert_eq!(
+            assemblable(&graph, &inventory),
+            vec!(("light red".to_string(), Ok(3)))
+        );
+    }
+
+    #[test]
+    fn test_assemblable_cycle() {
+        // Bags that are part of a cycle don't prevent other bags from being assembled.
+        let rules = "shiny gold bags contain 1 dark red bag.
+dark red bags contain 1 shiny gold bag.
+dark blue bags contain no other bags.";
+        let mut reader = BufReader::new(rules.as_bytes());
+        let graph = BagGraph::from_rules(&BagRule::from_reader(&mut reader)).unwrap();
+        let inventory = inventory(&[("shiny gold", 1), ("dark red", 1), ("dark blue", 1)]);
+        let result = assemblable(&graph, &inventory);
+        assert_eq!(result.len(), 3);
+        assert_eq!(result[0], ("dark blue".to_string(), Ok(1)));
+        assert!(matches!(result[1].1, Err(BagGraphError::Cycle { .. })));
+        assert!(matches!(result[2].1, Err(BagGraphError::Cycle { .. })));
+    }
+
+    #[test]
+    fn test_shortfall() {
+        let graph = read_graph();
+        let inventory = inventory(&[("shiny gold", 5), ("dark red", 5), ("dark blue", 15)]);
+        assert!(shortfall(&graph, &inventory, "shiny gold", 2)
+            .unwrap()
+            .is_empty());
+        let missing = shortfall(&graph, &inventory, "shiny gold", 3).unwrap();
+        assert_eq!(
+            missing.into_iter().collect::<Vec<(String, u128)>>(),
+            vec!(("dark blue".to_string(), 6), ("dark red".to_string(), 1))
+        );
+    }
+}
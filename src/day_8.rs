@@ -1,8 +1,67 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
 use std::io::BufRead;
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Clone)]
+mod assembler;
+mod bytecode;
+mod cfg;
+mod debugger;
+mod lint;
+mod repair;
+mod trace;
+
+pub use assembler::{assemble, disassemble, AssembleError};
+pub use bytecode::{decode, encode, BytecodeError, MAGIC, VERSION};
+pub use cfg::{basic_blocks, instruction_targets, targets, ControlFlowGraph};
+pub use debugger::{Command, Debugger};
+pub use lint::{cfg_to_dot, lint, Lint, LintKind, LintReport};
+pub use repair::{find_fix, find_repairs, Edit, MultiRepair, Repair, RepairOptions};
+pub use trace::{BlockProfile, Profile, Trace, TraceEntry, TraceMode};
+
+/// The number of registers in the processor: the accumulator and the registers `a` to `z`.
+pub const NUM_REGISTERS: usize = 27;
+
+/// The maximum number of instructions that `run` and `run_until` execute. Loops in programs with
+/// conditional jumps are only detected once the processor returns to the exact same state, which
+/// may never happen.
+pub const DEFAULT_FUEL: u64 = 1_000_000;
+
+/// A named register of the processor.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Register(u8);
+
+impl Register {
+    /// The accumulator, which is the only register used by the boot code.
+    pub const ACC: Register = Register(0);
+
+    /// Looks up a register by its name, i.e. `acc` or any of `a` to `z`.
+    pub fn from_name(name: &str) -> Option<Register> {
+        match name.as_bytes() {
+            b"acc" => Some(Register::ACC),
+            [c] if c.is_ascii_lowercase() => Some(Register(c - b'a' + 1)),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the register in the register file.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == Register::ACC {
+            write!(f, "acc")
+        } else {
+            write!(f, "{}", (b'a' + self.0 - 1) as char)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Operation {
     // Increases, or decreases the program's accumulator with the given argument.
     ACC,
@@ -10,15 +69,47 @@ pub enum Operation {
     JMP,
     // No operation.
     NOP,
+    // Adds the source register, or the argument if there is no source, to the register.
+    ADD(Register, Option<Register>),
+    // Multiplies the register by the source register, or the argument if there is no source.
+    MUL(Register, Option<Register>),
+    // Replaces the register by its remainder after division by the source register, or the
+    // argument if there is no source.
+    MOD(Register, Option<Register>),
+    // Jumps by the argument if the register is zero.
+    JZ(Register),
+    // Jumps by the argument if the register is not zero.
+    JNZ(Register),
+    // Jumps by the argument if the register is greater than zero.
+    JGZ(Register),
+    // Emits the value of the register, or the argument if there is no register.
+    OUT(Option<Register>),
+    // Halts the processor.
+    HLT,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct Instruction {
     operation: Operation,
     argument: i32,
 }
 
 impl Instruction {
+    pub fn new(operation: Operation, argument: i32) -> Self {
+        Instruction {
+            operation,
+            argument,
+        }
+    }
+
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    pub fn argument(&self) -> i32 {
+        self.argument
+    }
+
     pub fn from_reader<R: BufRead>(reader: &mut R) -> Vec<Instruction> {
         let mut instructions: Vec<Instruction> = Vec::new();
         loop {
@@ -46,30 +137,73 @@ impl Instruction {
 impl FromStr for Instruction {
     type Err = String;
 
+    /// Parses instructions of the form `op`, `op x` or `op r x`, where `x` is either an integer
+    /// or the name of a register. Operands may be separated by a comma.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut splits = s.split(' ');
-        let operation;
-        let argument: i32;
+        let mut splits = s
+            .split_whitespace()
+            .map(|split| split.trim_end_matches(','));
 
         // Attempt to parse the operation
-        match splits.next() {
-            Some("acc") => operation = Operation::ACC,
-            Some("jmp") => operation = Operation::JMP,
-            Some("nop") => operation = Operation::NOP,
-            Some(op) => return Err(format!("Unknown instruction: '{}'", op)),
+        let operation = match splits.next() {
+            Some(op) => op,
             _ => {
                 return Err(format!("Unable to parse instruction: '{}'", s));
             }
-        }
+        };
+        let operands: Vec<&str> = splits.collect();
 
-        // Attempt to parse the argument
-        match splits.next() {
-            Some(a) => match a.parse::<i32>() {
-                Ok(value) => argument = value,
-                Err(_) => return Err(format!("Failed to parse argument '{}' as i32.", a)),
-            },
-            _ => return Err(format!("Failed to parse argument from '{}'", s)),
-        }
+        let register = |operand: &str| {
+            Register::from_name(operand).ok_or_else(|| format!("Unknown register: '{}'", operand))
+        };
+        let argument = |operand: &str| {
+            operand
+                .parse::<i32>()
+                .map_err(|_| format!("Failed to parse argument '{}' as i32.", operand))
+        };
+        // Parses an operand that is either a register or an immediate value.
+        let value = |operand: &str| match Register::from_name(operand) {
+            Some(r) => Ok((Some(r), 0)),
+            None => argument(operand).map(|a| (None, a)),
+        };
+
+        let (operation, argument) = match (operation, operands.as_slice()) {
+            ("acc", [a]) => (Operation::ACC, argument(a)?),
+            ("jmp", [a]) => (Operation::JMP, argument(a)?),
+            ("nop", [a]) => (Operation::NOP, argument(a)?),
+            ("add", [r, x]) => {
+                let (source, a) = value(x)?;
+                (Operation::ADD(register(r)?, source), a)
+            }
+            ("mul", [r, x]) => {
+                let (source, a) = value(x)?;
+                (Operation::MUL(register(r)?, source), a)
+            }
+            ("mod", [r, x]) => {
+                let (source, a) = value(x)?;
+                (Operation::MOD(register(r)?, source), a)
+            }
+            ("jz", [r, a]) => (Operation::JZ(register(r)?), argument(a)?),
+            ("jnz", [r, a]) => (Operation::JNZ(register(r)?), argument(a)?),
+            ("jgz", [r, a]) => (Operation::JGZ(register(r)?), argument(a)?),
+            ("out", [x]) => {
+                let (source, a) = value(x)?;
+                (Operation::OUT(source), a)
+            }
+            ("hlt", []) => (Operation::HLT, 0),
+            ("acc", _)
+            | ("jmp", _)
+            | ("nop", _)
+            | ("add", _)
+            | ("mul", _)
+            | ("mod", _)
+            | ("jz", _)
+            | ("jnz", _)
+            | ("jgz", _)
+            | ("out", _)
+            | ("hlt", _) => return Err(format!("Wrong number of operands in '{}'", s)),
+            (op, _) => return Err(format!("Unknown instruction: '{}'", op)),
+        };
 
         Ok(Instruction {
             operation,
@@ -78,64 +212,438 @@ impl FromStr for Instruction {
     }
 }
 
+impl fmt::Display for Instruction {
+    /// Writes the instruction in the same form as it is parsed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Writes the source register if there is one, or the argument otherwise.
+        let value = |source: &Option<Register>| match source {
+            Some(r) => r.to_string(),
+            None => self.argument.to_string(),
+        };
+        match &self.operation {
+            Operation::ACC => write!(f, "acc {:+}", self.argument),
+            Operation::JMP => write!(f, "jmp {:+}", self.argument),
+            Operation::NOP => write!(f, "nop {:+}", self.argument),
+            Operation::ADD(r, source) => write!(f, "add {} {}", r, value(source)),
+            Operation::MUL(r, source) => write!(f, "mul {} {}", r, value(source)),
+            Operation::MOD(r, source) => write!(f, "mod {} {}", r, value(source)),
+            Operation::JZ(r) => write!(f, "jz {} {:+}", r, self.argument),
+            Operation::JNZ(r) => write!(f, "jnz {} {:+}", r, self.argument),
+            Operation::JGZ(r) => write!(f, "jgz {} {:+}", r, self.argument),
+            Operation::OUT(source) => write!(f, "out {}", value(source)),
+            Operation::HLT => write!(f, "hlt"),
+        }
+    }
+}
+
+/// A snapshot of the state of a processor, which can be used to restore it later.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ProcessorState {
+    registers: [i64; NUM_REGISTERS],
+    program_counter: i64,
+    output_len: usize,
+    halted: bool,
+}
+
+/// Describes why the processor stopped executing a program.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExecutionOutcome {
+    // The program counter points at the instruction directly after the program, or a `hlt`
+    // instruction was executed.
+    Terminated,
+    // The program will run forever. Contains the indices of the instructions in the loop, in the
+    // order in which they are executed.
+    LoopDetected { instructions: Vec<usize> },
+    // The program counter points outside of the program, other than directly after it.
+    JumpedOutOfBounds { program_counter: i64 },
+    // The maximum number of instructions was executed before the program stopped.
+    FuelExhausted,
+    // A `mod` instruction attempted to divide by zero.
+    DivisionByZero { program_counter: i64 },
+}
+
+/// Executes programs one instruction at a time.
+///
+/// Arithmetic on registers wraps around on overflow, for `acc`, `add` and `mul` alike. The
+/// program counter is wide enough that jumps never overflow, a jump that leaves the program is
+/// reported as `ExecutionOutcome::JumpedOutOfBounds` instead.
 pub struct Processor {
-    accumulator: i32,
-    program_counter: i32,
+    registers: [i64; NUM_REGISTERS],
+    program_counter: i64,
+    // The program that is being executed.
+    program: Vec<Instruction>,
+    // The values emitted by `out` instructions.
+    output: Vec<i64>,
+    // Whether a `hlt` instruction was executed.
+    halted: bool,
+    // What is recorded while executing instructions, if anything.
+    trace: Option<Trace>,
 }
 
 impl Processor {
     pub fn new() -> Self {
         Processor {
-            accumulator: 0,
+            registers: [0; NUM_REGISTERS],
             program_counter: 0,
+            program: Vec::new(),
+            output: Vec::new(),
+            halted: false,
+            trace: None,
         }
     }
 
-    /// Processes a list of instructions until a cycle is detected, as soon as a cycle has
-    /// been detected, the current value in the accumulator will be returned.
-    ///
-    /// If no loop will be found -1 will be returned instead of the value in the accumulator.
-    pub fn find_cycle(&mut self, instructions: &[Instruction]) -> (bool, i32) {
-        // Keep track of which instructions we've executed in order to detect the cycle.
-        let mut executed_instructions: HashSet<i32> = HashSet::new();
+    /// Creates a processor with `program` loaded.
+    pub fn with_program(program: Vec<Instruction>) -> Self {
+        let mut processor = Processor::new();
+        processor.program = program;
+        processor
+    }
 
-        // Reset the program counter before we begin.
+    /// Loads `program` and resets the state of the processor.
+    pub fn load(&mut self, program: Vec<Instruction>) {
+        self.program = program;
+        self.reset();
+    }
+
+    /// Resets the registers, program counter and output, but keeps the loaded program.
+    pub fn reset(&mut self) {
+        self.registers = [0; NUM_REGISTERS];
         self.program_counter = 0;
+        self.output.clear();
+        self.halted = false;
+        if let Some(trace) = &mut self.trace {
+            trace.clear(self.program.len());
+        }
+    }
+
+    /// Sets what the processor records while executing instructions, and discards anything that
+    /// was recorded before. Tracing is off by default.
+    pub fn set_trace_mode(&mut self, mode: TraceMode) {
+        self.trace = Trace::new(mode, self.program.len());
+    }
+
+    /// Returns what was recorded since the processor was last reset, if tracing is enabled.
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_ref()
+    }
+
+    /// Returns the loaded program.
+    pub fn program(&self) -> &[Instruction] {
+        &self.program
+    }
+
+    /// Replaces the instruction at `index` in the loaded program and returns the instruction
+    /// that was replaced, or `None` if `index` is out of bounds.
+    pub fn patch(&mut self, index: usize, instruction: Instruction) -> Option<Instruction> {
+        let old = self.program.get_mut(index)?;
+        Some(std::mem::replace(old, instruction))
+    }
+
+    /// Captures the current state of the processor.
+    pub fn snapshot(&self) -> ProcessorState {
+        ProcessorState {
+            registers: self.registers,
+            program_counter: self.program_counter,
+            output_len: self.output.len(),
+            halted: self.halted,
+        }
+    }
+
+    /// Restores a state that was captured by `snapshot`.
+    ///
+    /// Output that was emitted after the snapshot was taken is discarded.
+    pub fn restore(&mut self, state: &ProcessorState) {
+        self.registers = state.registers;
+        self.program_counter = state.program_counter;
+        self.output.truncate(state.output_len);
+        self.halted = state.halted;
+    }
+
+    /// Returns the index of the next instruction to execute.
+    pub fn program_counter(&self) -> i64 {
+        self.program_counter
+    }
+
+    /// Returns the value of the accumulator.
+    pub fn accumulator(&self) -> i64 {
+        self.registers[Register::ACC.index()]
+    }
+
+    /// Returns the value of `register`.
+    pub fn register(&self, register: Register) -> i64 {
+        self.registers[register.index()]
+    }
+
+    /// Returns the values that were emitted by `out` instructions.
+    pub fn output(&self) -> &[i64] {
+        &self.output
+    }
 
-        // Execute the instructions.
+    /// Returns why the processor can't execute the instruction at the program counter, or `None`
+    /// if it can.
+    pub fn status(&self) -> Option<ExecutionOutcome> {
+        if self.halted || self.program_counter as usize == self.program.len() {
+            Some(ExecutionOutcome::Terminated)
+        } else if self.program_counter < 0 || self.program_counter as usize > self.program.len() {
+            Some(ExecutionOutcome::JumpedOutOfBounds {
+                program_counter: self.program_counter,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Executes the instruction at the program counter.
+    ///
+    /// Returns `None` if the instruction was executed, or the reason why it couldn't be.
+    pub fn step(&mut self) -> Option<ExecutionOutcome> {
+        if let Some(outcome) = self.status() {
+            return Some(outcome);
+        }
+        let instruction = self.program[self.program_counter as usize];
+        if self.trace.is_none() {
+            return self.execute_instruction(&instruction);
+        }
+
+        let program_counter = self.program_counter as usize;
+        let accumulator_before = self.accumulator();
+        let outcome = self.execute_instruction(&instruction);
+        if outcome.is_none() {
+            let accumulator_after = self.accumulator();
+            if let Some(trace) = &mut self.trace {
+                trace.record(TraceEntry {
+                    program_counter,
+                    instruction,
+                    accumulator_before,
+                    accumulator_after,
+                });
+            }
+        }
+        outcome
+    }
+
+    /// Runs the program until it stops, a loop is detected, or `DEFAULT_FUEL` instructions have
+    /// been executed.
+    pub fn run(&mut self) -> ExecutionOutcome {
+        self.run_with_fuel(DEFAULT_FUEL)
+    }
+
+    /// Runs the program until it stops, a loop is detected, or `fuel` instructions have been
+    /// executed.
+    pub fn run_with_fuel(&mut self, fuel: u64) -> ExecutionOutcome {
+        self.execute(Some(fuel), |_| false).unwrap()
+    }
+
+    /// Runs the program until `predicate` holds before executing an instruction, in which case
+    /// `None` is returned, or until the program stops, a loop is detected, or `DEFAULT_FUEL`
+    /// instructions have been executed.
+    pub fn run_until<F: FnMut(&Processor) -> bool>(
+        &mut self,
+        predicate: F,
+    ) -> Option<ExecutionOutcome> {
+        self.execute(Some(DEFAULT_FUEL), predicate)
+    }
+
+    /// Runs the program, see `run_until`.
+    ///
+    /// If the program does not contain conditional jumps, then its control flow does not depend
+    /// on the state of the registers, and executing an instruction twice means that the program
+    /// loops. Otherwise a loop is only detected once the processor returns to the exact same
+    /// state, which is checked with Brent's algorithm so that no states need to be stored. The
+    /// processor is then somewhere inside of the loop, rather than at its start.
+    fn execute<F: FnMut(&Processor) -> bool>(
+        &mut self,
+        fuel: Option<u64>,
+        mut predicate: F,
+    ) -> Option<ExecutionOutcome> {
+        let conditional = self.program.iter().any(|i| {
+            matches!(
+                i.operation,
+                Operation::JZ(_) | Operation::JNZ(_) | Operation::JGZ(_)
+            )
+        });
+
+        // The instructions in the order in which they were executed, and for every instruction
+        // the position in `history` at which it was first seen.
+        let mut history: Vec<usize> = Vec::new();
+        let mut seen_instructions: HashMap<i64, usize> = HashMap::new();
+        // The state at the last checkpoint of Brent's algorithm, the number of instructions that
+        // were executed since, and the number of instructions after which the checkpoint moves.
+        let start = (self.program_counter, self.registers);
+        let mut checkpoint = start;
+        let mut since_checkpoint: usize = 0;
+        let mut power: usize = 1;
+        let mut remaining = fuel;
         loop {
-            if self.program_counter as usize >= instructions.len() {
-                // Program terminated without finding a loop.
-                return (false, self.accumulator);
+            if predicate(self) {
+                return None;
+            }
+            if let Some(outcome) = self.status() {
+                return Some(outcome);
             }
 
-            // Fetch an instruction
-            let instruction = &instructions[self.program_counter as usize];
+            if conditional {
+                let state = (self.program_counter, self.registers);
+                if since_checkpoint > 0 && state == checkpoint {
+                    return Some(ExecutionOutcome::LoopDetected {
+                        instructions: self.loop_instructions(start, since_checkpoint),
+                    });
+                }
+                if since_checkpoint == power {
+                    checkpoint = state;
+                    power *= 2;
+                    since_checkpoint = 0;
+                }
+            } else if let Some(start) =
+                seen_instructions.insert(self.program_counter, history.len())
+            {
+                return Some(ExecutionOutcome::LoopDetected {
+                    instructions: history[start..].to_vec(),
+                });
+            }
+
+            match remaining {
+                Some(0) => return Some(ExecutionOutcome::FuelExhausted),
+                Some(n) => remaining = Some(n - 1),
+                None => {}
+            }
 
-            // Check if the instruction has been executed previously.
-            if executed_instructions.contains(&self.program_counter) {
-                // We've found the cycle, return the value of the accumulator.
-                return (true, self.accumulator);
+            if !conditional {
+                history.push(self.program_counter as usize);
+            }
+            if let Some(outcome) = self.step() {
+                return Some(outcome);
             }
+            since_checkpoint += 1;
+        }
+    }
 
-            // Track which instructions were executed.
-            executed_instructions.insert(self.program_counter);
+    /// Returns the instructions of the loop of `len` instructions that the processor eventually
+    /// enters from the `start` state, in the order in which they are executed from the first
+    /// state in the loop.
+    fn loop_instructions(&self, start: (i64, [i64; NUM_REGISTERS]), len: usize) -> Vec<usize> {
+        let scratch = || {
+            let mut processor = Processor::with_program(self.program.clone());
+            processor.program_counter = start.0;
+            processor.registers = start.1;
+            processor
+        };
+        let state = |p: &Processor| (p.program_counter, p.registers);
+
+        // Move one processor `len` instructions ahead, the two processors then first reach the
+        // same state at the start of the loop.
+        let mut behind = scratch();
+        let mut ahead = scratch();
+        for _ in 0..len {
+            ahead.step();
+        }
+        while state(&behind) != state(&ahead) {
+            behind.step();
+            ahead.step();
+        }
 
-            // Execute the instruction.
-            self.execute_instruction(&instruction);
+        let mut instructions = Vec::with_capacity(len);
+        for _ in 0..len {
+            instructions.push(behind.program_counter as usize);
+            behind.step();
         }
+        instructions
+    }
+
+    /// Processes a list of instructions until a cycle is detected, as soon as a cycle has
+    /// been detected, the current value in the accumulator will be returned.
+    ///
+    /// The first entry of the returned tuple is `false` if the program stopped instead. As in the
+    /// boot code, a cycle is detected as soon as an instruction is about to be executed twice,
+    /// use `run` for programs with conditional jumps. The loaded program is left untouched.
+    ///
+    /// Panics if the accumulator does not fit in the i32 of the boot code.
+    pub fn find_cycle(&mut self, instructions: &[Instruction]) -> (bool, i32) {
+        self.reset();
+        let mut executed_instructions: HashSet<i64> = HashSet::new();
+        let cycle = loop {
+            if self.halted
+                || self.program_counter < 0
+                || self.program_counter as usize >= instructions.len()
+            {
+                break false;
+            }
+            if !executed_instructions.insert(self.program_counter) {
+                break true;
+            }
+            let instruction = &instructions[self.program_counter as usize];
+            if self.execute_instruction(instruction).is_some() {
+                break false;
+            }
+        };
+        let accumulator = i32::try_from(self.accumulator()).unwrap_or_else(|_| {
+            panic!(
+                "The accumulator {} does not fit in an i32",
+                self.accumulator()
+            )
+        });
+        (cycle, accumulator)
     }
 
-    /// Executes a single instruction.
-    fn execute_instruction(&mut self, instruction: &Instruction) {
+    /// Executes a single instruction, and returns an outcome if the instruction faulted.
+    fn execute_instruction(&mut self, instruction: &Instruction) -> Option<ExecutionOutcome> {
+        let argument = instruction.argument as i64;
+        // Resolves the source operand of an instruction.
+        let value = |registers: &[i64], source: Option<Register>| match source {
+            Some(r) => registers[r.index()],
+            None => argument,
+        };
+
         // Execute the instruction.
         match instruction.operation {
             Operation::ACC => {
-                self.accumulator += instruction.argument;
+                self.registers[Register::ACC.index()] =
+                    self.registers[Register::ACC.index()].wrapping_add(argument);
                 self.program_counter += 1
             }
-            Operation::JMP => self.program_counter += instruction.argument,
+            Operation::JMP => self.program_counter += argument,
             Operation::NOP => self.program_counter += 1,
+            Operation::ADD(r, source) => {
+                self.registers[r.index()] =
+                    self.registers[r.index()].wrapping_add(value(&self.registers, source));
+                self.program_counter += 1
+            }
+            Operation::MUL(r, source) => {
+                self.registers[r.index()] =
+                    self.registers[r.index()].wrapping_mul(value(&self.registers, source));
+                self.program_counter += 1
+            }
+            Operation::MOD(r, source) => {
+                let divisor = value(&self.registers, source);
+                match self.registers[r.index()].checked_rem_euclid(divisor) {
+                    Some(remainder) => self.registers[r.index()] = remainder,
+                    None => {
+                        return Some(ExecutionOutcome::DivisionByZero {
+                            program_counter: self.program_counter,
+                        })
+                    }
+                }
+                self.program_counter += 1
+            }
+            Operation::JZ(r) => self.jump_if(self.registers[r.index()] == 0, instruction),
+            Operation::JNZ(r) => self.jump_if(self.registers[r.index()] != 0, instruction),
+            Operation::JGZ(r) => self.jump_if(self.registers[r.index()] > 0, instruction),
+            Operation::OUT(source) => {
+                self.output.push(value(&self.registers, source));
+                self.program_counter += 1
+            }
+            Operation::HLT => self.halted = true,
+        }
+        None
+    }
+
+    /// Jumps by the argument of the instruction if `condition` holds, or continues with the
+    /// next instruction otherwise.
+    fn jump_if(&mut self, condition: bool, instruction: &Instruction) {
+        if condition {
+            self.program_counter += instruction.argument as i64
+        } else {
+            self.program_counter += 1
         }
     }
 }
@@ -146,49 +654,6 @@ impl Default for Processor {
     }
 }
 
-/// Finds the fix in the boot code by testing updating instructions.
-///
-/// Returns -1 if a solution could not be found, or the value of the accumulator after program
-/// termination if a solution could be found.
-pub fn find_fix(instructions: &[Instruction]) -> i32 {
-    // In order to find the fix, we need to find an instruction that needs to be changed from
-    // "jmp" to "nop" or vice versa. In order to do this we'll simply convert a nop statement
-    // into a jmp statement, or vice versa and test whether the program works as expected.
-    for (idx, instruction) in instructions.iter().enumerate() {
-        if instruction.operation == Operation::ACC {
-            // ACC operations were not corrupted.
-            continue;
-        }
-        // Clone the list of instructions
-        let mut modified_instructions = instructions.to_owned();
-        // Modify the instruction at index.
-        let instruction = &modified_instructions[idx];
-        match instruction.operation {
-            Operation::JMP => {
-                modified_instructions[idx] = Instruction {
-                    operation: Operation::NOP,
-                    argument: instruction.argument,
-                }
-            }
-            Operation::NOP => {
-                modified_instructions[idx] = Instruction {
-                    operation: Operation::JMP,
-                    argument: instruction.argument,
-                }
-            }
-            _ => {}
-        }
-
-        // Test whether the program works as expected.
-        let mut processor = Processor::new();
-        if let (false, acc) = processor.find_cycle(&modified_instructions) {
-            return acc;
-        }
-    }
-    // No solution
-    -1
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,8 +671,8 @@ jmp -4
 acc +6
 ";
         let mut reader = BufReader::new(instructions_input.as_bytes());
-        let instructions = Instruction::from_reader(&mut reader);
-        return instructions;
+
+        Instruction::from_reader(&mut reader)
     }
 
     #[test]
@@ -255,9 +720,175 @@ acc +6
         assert_eq!(processor.find_cycle(&instructions), (true, 5));
     }
 
+    #[test]
+    fn test_extended_instructions() {
+        let program = "add a 10
+add b, 1
+mul b 2
+mul b a
+add b 1004
+mod b 1000
+out b
+add acc b
+jgz acc +2
+out 99
+jnz c -3
+jz a +2
+hlt
+out 42";
+        let instructions = Instruction::from_reader(&mut BufReader::new(program.as_bytes()));
+        assert_eq!(instructions.len(), 14);
+        assert_eq!(
+            instructions[1],
+            Instruction {
+                operation: Operation::ADD(Register::from_name("b").unwrap(), None),
+                argument: 1
+            }
+        );
+
+        let mut processor = Processor::new();
+        assert_eq!(processor.find_cycle(&instructions), (false, 24));
+        assert_eq!(processor.register(Register::from_name("b").unwrap()), 24);
+        assert_eq!(processor.output(), &[24]);
+
+        // Every instruction is written back out in the form in which it was parsed.
+        for (line, instruction) in program.lines().zip(instructions.iter()) {
+            assert_eq!(instruction.to_string(), line.replace(',', ""));
+        }
+    }
+
+    #[test]
+    fn test_step_and_run() {
+        let mut processor = Processor::with_program(read_instructions());
+        assert_eq!(processor.step(), None);
+        assert_eq!(processor.step(), None);
+        assert_eq!(processor.accumulator(), 1);
+        assert_eq!(processor.program_counter(), 2);
+
+        assert_eq!(processor.run_until(|p| p.program_counter() == 4), None);
+        assert_eq!(processor.accumulator(), 5);
+        assert_eq!(
+            processor.run(),
+            ExecutionOutcome::LoopDetected {
+                instructions: vec!(4, 1, 2, 6, 7, 3)
+            }
+        );
+        assert_eq!(processor.accumulator(), 10);
+
+        processor.reset();
+        assert_eq!(processor.run_with_fuel(3), ExecutionOutcome::FuelExhausted);
+        assert_eq!(processor.program_counter(), 6);
+
+        // Running the cycle detection twice yields the same result.
+        let mut processor = Processor::new();
+        assert_eq!(processor.find_cycle(&read_instructions()), (true, 5));
+        assert_eq!(processor.find_cycle(&read_instructions()), (true, 5));
+        assert!(processor.program().is_empty());
+
+        // A counter that never returns to the same state runs out of fuel.
+        let program = Instruction::from_reader(&mut BufReader::new("add a 1\njgz a -1".as_bytes()));
+        let mut processor = Processor::with_program(program);
+        assert_eq!(processor.run(), ExecutionOutcome::FuelExhausted);
+        assert_eq!(
+            processor.register(Register::from_name("a").unwrap()),
+            500_000
+        );
+    }
+
+    #[test]
+    fn test_execution_outcomes() {
+        let run = |program: &str| {
+            let instructions = Instruction::from_reader(&mut BufReader::new(program.as_bytes()));
+            let mut processor = Processor::with_program(instructions);
+            (processor.run(), processor.accumulator())
+        };
+        assert_eq!(
+            run("acc +1\njmp +1\nacc +2"),
+            (ExecutionOutcome::Terminated, 3)
+        );
+        assert_eq!(
+            run("acc +1\nhlt\nacc +2"),
+            (ExecutionOutcome::Terminated, 1)
+        );
+        assert_eq!(
+            run("acc +1\njmp -2"),
+            (
+                ExecutionOutcome::JumpedOutOfBounds {
+                    program_counter: -1
+                },
+                1
+            )
+        );
+        assert_eq!(
+            run("acc +1\njmp +2"),
+            (
+                ExecutionOutcome::JumpedOutOfBounds { program_counter: 3 },
+                1
+            )
+        );
+        assert_eq!(
+            run("mod a 0"),
+            (ExecutionOutcome::DivisionByZero { program_counter: 0 }, 0)
+        );
+
+        // Jumps beyond the range of the argument are out of bounds, and arithmetic wraps around.
+        assert_eq!(
+            run("acc +1\njmp +2147483647"),
+            (
+                ExecutionOutcome::JumpedOutOfBounds {
+                    program_counter: 2147483648
+                },
+                1
+            )
+        );
+        assert_eq!(
+            run("add acc 1\nadd b 63\nmul acc 2\nadd b -1\njnz b -2\nacc -1"),
+            (ExecutionOutcome::Terminated, i64::MAX)
+        );
+
+        // A loop that counts down terminates, while a loop that returns the processor to the
+        // exact same state is detected.
+        assert_eq!(
+            run("add a 3\nadd acc 2\nadd a -1\njnz a -2"),
+            (ExecutionOutcome::Terminated, 6)
+        );
+        assert_eq!(
+            run("add a 3\njnz b +2\njmp -1"),
+            (
+                ExecutionOutcome::LoopDetected {
+                    instructions: vec!(1, 2)
+                },
+                0
+            )
+        );
+        // The loop is reported from its first instruction, wherever it was detected.
+        assert_eq!(
+            run("add a 2\nadd b 1\njnz a +1\nnop +0\nnop +0\njmp -3"),
+            (
+                ExecutionOutcome::LoopDetected {
+                    instructions: vec!(2, 3, 4, 5)
+                },
+                0
+            )
+        );
+    }
+
+    #[test]
+    fn test_instruction_errors() {
+        assert!(Instruction::from_str("add 1 a").is_err());
+        assert!(Instruction::from_str("add a").is_err());
+        assert!(Instruction::from_str("hlt 1").is_err());
+        assert!(Instruction::from_str("jz q1 +1").is_err());
+        assert!(Instruction::from_str("foo +1").is_err());
+        assert_eq!(
+            Instruction::from_str("nop -4").unwrap().to_string(),
+            "nop -4"
+        );
+    }
+
     #[test]
     fn test_find_fix() {
         let instructions = read_instructions();
-        assert_eq!(find_fix(&instructions), 8);
+        assert_eq!(find_fix(&instructions).map(|r| r.accumulator), Ok(8));
     }
 }
@@ -0,0 +1,307 @@
+use super::{Instruction, Operation, Register};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// An error that occurred while assembling a program.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AssembleError {
+    // The line on which the error occurred, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A line of source that contains an instruction.
+struct SourceLine<'a> {
+    line: usize,
+    text: &'a str,
+}
+
+/// Assembles a program from source.
+///
+/// Besides instructions in the form accepted by `Instruction::from_str`, the source may contain:
+///
+/// * comments, which start with `#` or `;` and run until the end of the line;
+/// * blank lines;
+/// * labels, written as `name:` either on their own line or in front of an instruction. A label
+///   refers to the next instruction, and can be used as the target of `jmp`, `nop`, `jz`, `jnz`
+///   and `jgz` instead of a relative offset;
+/// * constants, written as `const NAME = value`, which can be used instead of any integer operand
+///   and may be prefixed with a sign.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, AssembleError> {
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut constants: HashMap<&str, i32> = HashMap::new();
+    let mut lines: Vec<SourceLine> = Vec::new();
+
+    // Collect all labels and constants first, so that labels can be used before they are defined.
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let error = |message: String| AssembleError { line, message };
+        let mut text = text
+            .find(['#', ';'])
+            .map_or(text, |comment| &text[..comment])
+            .trim();
+
+        if let Some(definition) = text.strip_prefix("const ") {
+            let (name, value) = definition
+                .split_once('=')
+                .ok_or_else(|| error(format!("Expected 'const NAME = value', got '{}'", text)))?;
+            let name = name.trim();
+            check_symbol(name).map_err(error)?;
+            let value = value.trim().parse::<i32>().map_err(|_| {
+                error(format!(
+                    "Failed to parse constant '{}' as i32",
+                    value.trim()
+                ))
+            })?;
+            if labels.contains_key(name) || constants.insert(name, value).is_some() {
+                return Err(error(format!("'{}' is defined more than once", name)));
+            }
+            continue;
+        }
+
+        while let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            check_symbol(label).map_err(error)?;
+            if constants.contains_key(label) || labels.insert(label, lines.len()).is_some() {
+                return Err(error(format!("'{}' is defined more than once", label)));
+            }
+            text = rest.trim();
+        }
+
+        if !text.is_empty() {
+            lines.push(SourceLine { line, text });
+        }
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, source)| {
+            resolve(index, source.text, &labels, &constants)
+                .and_then(|text| Instruction::from_str(&text))
+                .map_err(|message| AssembleError {
+                    line: source.line,
+                    message,
+                })
+        })
+        .collect()
+}
+
+/// Checks whether `name` can be used as the name of a label or constant.
+fn check_symbol(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!("'{}' is not a valid name", name));
+    }
+    if Register::from_name(name).is_some() {
+        return Err(format!("'{}' is the name of a register", name));
+    }
+    Ok(())
+}
+
+/// Replaces labels and constants in the instruction at `index` by their values.
+fn resolve(
+    index: usize,
+    text: &str,
+    labels: &HashMap<&str, usize>,
+    constants: &HashMap<&str, i32>,
+) -> Result<String, String> {
+    let mut words = text
+        .split_whitespace()
+        .map(|word| word.trim_end_matches(','));
+    let operation = words.next().unwrap_or_default();
+    let is_jump = matches!(operation, "jmp" | "nop" | "jz" | "jnz" | "jgz");
+    let mut resolved = vec![operation.to_string()];
+    for word in words {
+        if Register::from_name(word).is_some() || word.parse::<i32>().is_ok() {
+            resolved.push(word.to_string());
+            continue;
+        }
+        let (sign, name) = match word.strip_prefix('-') {
+            Some(name) => (-1, name),
+            None => (1, word.strip_prefix('+').unwrap_or(word)),
+        };
+        let value = match (constants.get(name), labels.get(name)) {
+            (Some(value), _) => match value.checked_mul(sign) {
+                Some(value) => value,
+                None => return Err(format!("Constant '{}' can not be negated", name)),
+            },
+            (None, Some(&target)) if is_jump && name == word => target as i32 - index as i32,
+            (None, Some(_)) if is_jump => {
+                return Err(format!("Label '{}' can not have a sign", name));
+            }
+            (None, Some(_)) => {
+                return Err(format!(
+                    "Label '{}' can only be used as the target of a jump",
+                    name
+                ));
+            }
+            (None, None) => return Err(format!("Unknown label or constant: '{}'", name)),
+        };
+        resolved.push(format!("{:+}", value));
+    }
+    Ok(resolved.join(" "))
+}
+
+/// Renders a program as source, replacing the offsets of jumps by synthesized labels.
+///
+/// Jumps to locations outside of the program, except for the location directly after the
+/// program, keep their relative offset. `assemble` turns the output back into the same program.
+pub fn disassemble(program: &[Instruction]) -> String {
+    let target = |index: usize, instruction: &Instruction| {
+        let target = index as i64 + instruction.argument as i64;
+        let is_jump = matches!(
+            instruction.operation,
+            Operation::JMP | Operation::JZ(_) | Operation::JNZ(_) | Operation::JGZ(_)
+        );
+        if is_jump && target >= 0 && target as usize <= program.len() {
+            Some(target as usize)
+        } else {
+            None
+        }
+    };
+    let targets: BTreeSet<usize> = program
+        .iter()
+        .enumerate()
+        .filter_map(|(index, instruction)| target(index, instruction))
+        .collect();
+    // Labels are numbered in the order in which they appear in the program.
+    let labels: HashMap<usize, String> = targets
+        .iter()
+        .enumerate()
+        .map(|(n, &target)| (target, format!("L{}", n)))
+        .collect();
+
+    let mut lines: Vec<String> = Vec::new();
+    for index in 0..=program.len() {
+        if let Some(label) = labels.get(&index) {
+            lines.push(format!("{}:", label));
+        }
+        let instruction = match program.get(index) {
+            Some(instruction) => instruction,
+            None => break,
+        };
+        let text = instruction.to_string();
+        match target(index, instruction) {
+            Some(target) => {
+                // The offset is always the last operand of a jump.
+                let (operands, _) = text.rsplit_once(' ').unwrap();
+                lines.push(format!("    {} {}", operands, labels[&target]));
+            }
+            None => lines.push(format!("    {}", text)),
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn program(source: &str) -> Vec<Instruction> {
+        Instruction::from_reader(&mut BufReader::new(source.as_bytes()))
+    }
+
+    #[test]
+    fn test_assemble() {
+        let source = "# Counts down from 3.
+const START = 3
+const STEP = 1
+
+    add a START
+loop_start:
+    out a
+    add a -STEP    ; decrement
+    jnz a, loop_start
+    jmp end
+    acc +99
+end: hlt
+";
+        assert_eq!(
+            assemble(source),
+            Ok(program(
+                "add a 3\nout a\nadd a -1\njnz a -2\njmp +2\nacc +99\nhlt"
+            ))
+        );
+
+        // Labels may refer to the end of the program, and may precede their definition.
+        assert_eq!(
+            assemble("start: nop done\njmp start\ndone:"),
+            Ok(program("nop +2\njmp -1"))
+        );
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let error = |line: usize, message: &str| {
+            Err(AssembleError {
+                line,
+                message: message.to_string(),
+            })
+        };
+        assert_eq!(
+            assemble("acc +1\n\njmp nowhere"),
+            error(3, "Unknown label or constant: 'nowhere'")
+        );
+        assert_eq!(
+            assemble("top: acc +1\ntop: acc +1"),
+            error(2, "'top' is defined more than once")
+        );
+        assert_eq!(
+            assemble("a: acc +1"),
+            error(1, "'a' is the name of a register")
+        );
+        assert_eq!(
+            assemble("start: acc start"),
+            error(1, "Label 'start' can only be used as the target of a jump")
+        );
+        assert_eq!(
+            assemble("const X = 1.5"),
+            error(1, "Failed to parse constant '1.5' as i32")
+        );
+        assert_eq!(
+            assemble("acc"),
+            error(1, "Wrong number of operands in 'acc'")
+        );
+        assert_eq!(
+            assemble("const MIN = -2147483648\nacc -MIN"),
+            error(2, "Constant 'MIN' can not be negated")
+        );
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let instructions =
+            program("nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6\njz a +2\njmp +50");
+        let source = disassemble(&instructions);
+        assert_eq!(
+            source,
+            "    nop +0
+L0:
+    acc +1
+    jmp L2
+L1:
+    acc +3
+    jmp L0
+    acc -99
+L2:
+    acc +1
+    jmp L1
+    acc +6
+    jz a L3
+    jmp +50
+L3:"
+        );
+        assert_eq!(assemble(&source), Ok(instructions));
+    }
+}
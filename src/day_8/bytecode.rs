@@ -0,0 +1,371 @@
+use super::{Instruction, Operation, Processor, Register, NUM_REGISTERS};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// The bytes every bytecode file starts with.
+pub const MAGIC: &[u8; 4] = b"BOOT";
+/// The version of the bytecode format that is written by `encode`.
+pub const VERSION: u8 = 1;
+
+// The header consists of the magic, the version and the length of the payload as a u32.
+const HEADER_LEN: usize = 4 + 1 + 4;
+// The checksum is a u32 after the payload.
+const CHECKSUM_LEN: usize = 4;
+
+/// An error that occurred while decoding bytecode.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BytecodeError {
+    // The file does not start with `MAGIC`.
+    BadMagic,
+    UnsupportedVersion(u8),
+    // The file is shorter than its header says it should be.
+    Truncated { expected: usize, found: usize },
+    // The file is longer than its header says it should be.
+    TrailingBytes { expected: usize, found: usize },
+    ChecksumMismatch { expected: u32, found: u32 },
+    // The payload is malformed at the given offset.
+    Malformed { offset: usize, message: String },
+}
+
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytecodeError::BadMagic => write!(f, "Not a bytecode file: missing magic bytes"),
+            BytecodeError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported bytecode version {}, expected version {}",
+                version, VERSION
+            ),
+            BytecodeError::Truncated { expected, found } => write!(
+                f,
+                "Bytecode is truncated: expected {} bytes, but found {}",
+                expected, found
+            ),
+            BytecodeError::TrailingBytes { expected, found } => write!(
+                f,
+                "Bytecode has trailing bytes: expected {} bytes, but found {}",
+                expected, found
+            ),
+            BytecodeError::ChecksumMismatch { expected, found } => write!(
+                f,
+                "Bytecode is corrupt: expected checksum {:08x}, but found {:08x}",
+                expected, found
+            ),
+            BytecodeError::Malformed { offset, message } => {
+                write!(f, "Bytecode is malformed at offset {}: {}", offset, message)
+            }
+        }
+    }
+}
+
+/// Encodes a program as bytecode.
+///
+/// The payload contains the number of instructions followed by the instructions, each of which
+/// is encoded as an opcode, its register operands and its argument. Opcodes, operands and
+/// arguments are LEB128 varints, and arguments are zigzag encoded.
+pub fn encode(program: &[Instruction]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    write_varint(&mut payload, program.len() as u64);
+    for instruction in program {
+        let (opcode, operands) = opcode(&instruction.operation);
+        write_varint(&mut payload, opcode);
+        for operand in operands {
+            write_varint(&mut payload, operand);
+        }
+        write_varint(&mut payload, zigzag(instruction.argument));
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len() + CHECKSUM_LEN);
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+    let checksum = checksum(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+/// Decodes a program from bytecode that was produced by `encode`.
+pub fn decode(bytes: &[u8]) -> Result<Vec<Instruction>, BytecodeError> {
+    // Input shorter than the magic bytes is only truncated if it could be the start of them.
+    let prefix = bytes.len().min(MAGIC.len());
+    if bytes[..prefix] != MAGIC[..prefix] {
+        return Err(BytecodeError::BadMagic);
+    }
+    if bytes.len() < HEADER_LEN {
+        return Err(BytecodeError::Truncated {
+            expected: HEADER_LEN,
+            found: bytes.len(),
+        });
+    }
+    if bytes[4] != VERSION {
+        return Err(BytecodeError::UnsupportedVersion(bytes[4]));
+    }
+    let payload_len = u32::from_le_bytes([bytes[5], bytes[6], bytes[7], bytes[8]]) as usize;
+    let expected = HEADER_LEN + payload_len + CHECKSUM_LEN;
+    if bytes.len() < expected {
+        return Err(BytecodeError::Truncated {
+            expected,
+            found: bytes.len(),
+        });
+    }
+    if bytes.len() > expected {
+        return Err(BytecodeError::TrailingBytes {
+            expected,
+            found: bytes.len(),
+        });
+    }
+    let (contents, stored) = bytes.split_at(expected - CHECKSUM_LEN);
+    let stored = u32::from_le_bytes([stored[0], stored[1], stored[2], stored[3]]);
+    let computed = checksum(contents);
+    if stored != computed {
+        return Err(BytecodeError::ChecksumMismatch {
+            expected: stored,
+            found: computed,
+        });
+    }
+
+    let mut reader = Reader {
+        bytes: contents,
+        offset: HEADER_LEN,
+    };
+    let count = reader.varint()?;
+    let mut program = Vec::new();
+    for _ in 0..count {
+        program.push(reader.instruction()?);
+    }
+    if reader.offset != contents.len() {
+        return Err(reader.error("Unexpected data after the last instruction".to_string()));
+    }
+    Ok(program)
+}
+
+impl Processor {
+    /// Loads a program from bytecode, see `decode`.
+    pub fn load_bytecode(&mut self, bytes: &[u8]) -> Result<(), BytecodeError> {
+        self.load(decode(bytes)?);
+        Ok(())
+    }
+}
+
+/// Returns the opcode of an operation and its register operands. Source operands are encoded as
+/// 0 for an immediate value, or as the index of the register plus one.
+fn opcode(operation: &Operation) -> (u64, Vec<u64>) {
+    let register = |r: &Register| r.index() as u64;
+    let source = |s: &Option<Register>| s.map_or(0, |r| r.index() as u64 + 1);
+    match operation {
+        Operation::ACC => (0, vec![]),
+        Operation::JMP => (1, vec![]),
+        Operation::NOP => (2, vec![]),
+        Operation::ADD(r, s) => (3, vec![register(r), source(s)]),
+        Operation::MUL(r, s) => (4, vec![register(r), source(s)]),
+        Operation::MOD(r, s) => (5, vec![register(r), source(s)]),
+        Operation::JZ(r) => (6, vec![register(r)]),
+        Operation::JNZ(r) => (7, vec![register(r)]),
+        Operation::JGZ(r) => (8, vec![register(r)]),
+        Operation::OUT(s) => (9, vec![source(s)]),
+        Operation::HLT => (10, vec![]),
+    }
+}
+
+/// Reads instructions from the payload of a bytecode file.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, message: String) -> BytecodeError {
+        BytecodeError::Malformed {
+            offset: self.offset,
+            message,
+        }
+    }
+
+    fn varint(&mut self) -> Result<u64, BytecodeError> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = match self.bytes.get(self.offset) {
+                Some(&byte) => byte,
+                None => return Err(self.error("Unexpected end of payload".to_string())),
+            };
+            // Only the lowest bit of the tenth byte still fits in a u64.
+            if shift == 63 && byte > 1 {
+                return Err(self.error("Varint overflows 64 bits".to_string()));
+            }
+            self.offset += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(self.error("Varint is too long".to_string()))
+    }
+
+    fn register(&mut self) -> Result<Register, BytecodeError> {
+        let index = self.varint()?;
+        if index as usize >= NUM_REGISTERS {
+            return Err(self.error(format!("Invalid register {}", index)));
+        }
+        Ok(Register(index as u8))
+    }
+
+    fn source(&mut self) -> Result<Option<Register>, BytecodeError> {
+        let source = self.varint()?;
+        if source as usize > NUM_REGISTERS {
+            return Err(self.error(format!("Invalid source operand {}", source)));
+        }
+        Ok(source.checked_sub(1).map(|index| Register(index as u8)))
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, BytecodeError> {
+        let operation = match self.varint()? {
+            0 => Operation::ACC,
+            1 => Operation::JMP,
+            2 => Operation::NOP,
+            3 => Operation::ADD(self.register()?, self.source()?),
+            4 => Operation::MUL(self.register()?, self.source()?),
+            5 => Operation::MOD(self.register()?, self.source()?),
+            6 => Operation::JZ(self.register()?),
+            7 => Operation::JNZ(self.register()?),
+            8 => Operation::JGZ(self.register()?),
+            9 => Operation::OUT(self.source()?),
+            10 => Operation::HLT,
+            opcode => return Err(self.error(format!("Unknown opcode {}", opcode))),
+        };
+        let argument = unzigzag(self.varint()?);
+        let argument = i32::try_from(argument)
+            .map_err(|_| self.error(format!("Argument {} does not fit in an i32", argument)))?;
+        Ok(Instruction::new(operation, argument))
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Maps signed integers onto unsigned integers, such that small magnitudes have short encodings.
+fn zigzag(value: i32) -> u64 {
+    let value = value as i64;
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// The 32-bit FNV-1a hash of `bytes`.
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c_9dc5, |hash, &byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn program() -> Vec<Instruction> {
+        let source = "nop +0\nacc +1\njmp +4\nacc -3000000\njmp -3\nadd a 5\nmul b c\nmod acc z
+jnz a -2\njgz b +7\nout a\nout -12\nhlt";
+        Instruction::from_reader(&mut BufReader::new(source.as_bytes()))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let program = program();
+        let bytes = encode(&program);
+        assert_eq!(&bytes[..5], b"BOOT\x01");
+        assert_eq!(decode(&bytes), Ok(program.clone()));
+        assert_eq!(decode(&encode(&[])), Ok(vec![]));
+        assert_eq!(
+            decode(&encode(&[Instruction::new(Operation::ACC, i32::MIN)])),
+            Ok(vec![Instruction::new(Operation::ACC, i32::MIN)])
+        );
+
+        let mut processor = Processor::new();
+        processor.load_bytecode(&bytes).unwrap();
+        assert_eq!(processor.program(), program.as_slice());
+    }
+
+    #[test]
+    fn test_zigzag() {
+        for &value in &[0, 1, -1, 63, -64, i32::MAX, i32::MIN] {
+            assert_eq!(unzigzag(zigzag(value)), value as i64);
+        }
+        assert_eq!(zigzag(-1), 1);
+        assert_eq!(zigzag(1), 2);
+    }
+
+    #[test]
+    fn test_varint() {
+        let read = |bytes: &[u8]| Reader { bytes, offset: 0 }.varint();
+        let mut max = Vec::new();
+        write_varint(&mut max, u64::MAX);
+        assert_eq!(max.len(), 10);
+        assert_eq!(read(&max), Ok(u64::MAX));
+
+        let mut overflow = max.clone();
+        overflow[9] = 0x02;
+        assert!(matches!(
+            read(&overflow),
+            Err(BytecodeError::Malformed { offset: 9, .. })
+        ));
+        assert!(matches!(
+            read(&[0xff; 11]),
+            Err(BytecodeError::Malformed { offset: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let bytes = encode(&program());
+
+        assert_eq!(decode(b"acc +1\n"), Err(BytecodeError::BadMagic));
+        assert_eq!(decode(b"ab"), Err(BytecodeError::BadMagic));
+        assert_eq!(
+            decode(&bytes[..2]),
+            Err(BytecodeError::Truncated {
+                expected: 9,
+                found: 2
+            })
+        );
+        assert_eq!(
+            decode(&bytes[..7]),
+            Err(BytecodeError::Truncated {
+                expected: 9,
+                found: 7
+            })
+        );
+        assert_eq!(
+            decode(&bytes[..bytes.len() - 1]),
+            Err(BytecodeError::Truncated {
+                expected: bytes.len(),
+                found: bytes.len() - 1
+            })
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(
+            decode(&trailing),
+            Err(BytecodeError::TrailingBytes { .. })
+        ));
+
+        let mut version = bytes.clone();
+        version[4] = 2;
+        assert_eq!(decode(&version), Err(BytecodeError::UnsupportedVersion(2)));
+
+        let mut corrupt = bytes.clone();
+        corrupt[12] ^= 0x10;
+        assert!(matches!(
+            decode(&corrupt),
+            Err(BytecodeError::ChecksumMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+use super::{Instruction, Operation};
+use std::collections::{BTreeSet, VecDeque};
+use std::ops::Range;
+
+/// The control-flow graph of a program.
+///
+/// Nodes are instruction indices, and the additional node `exit()` (equal to the length of the
+/// program) represents termination. Jumps to any other location outside of the program have no
+/// edge, as the processor faults when it takes them.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ControlFlowGraph {
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+}
+
+impl ControlFlowGraph {
+    pub fn from_program(program: &[Instruction]) -> Self {
+        let exit = program.len();
+        let successors: Vec<Vec<usize>> = (0..=exit)
+            .map(|index| {
+                if index == exit {
+                    return Vec::new();
+                }
+                targets(program, index)
+                    .into_iter()
+                    .filter(|&target| target >= 0 && target as usize <= exit)
+                    .map(|target| target as usize)
+                    .collect()
+            })
+            .collect();
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); exit + 1];
+        for (index, targets) in successors.iter().enumerate() {
+            for &target in targets {
+                predecessors[target].push(index);
+            }
+        }
+        ControlFlowGraph {
+            successors,
+            predecessors,
+        }
+    }
+
+    /// Returns the node that represents termination of the program.
+    pub fn exit(&self) -> usize {
+        self.successors.len() - 1
+    }
+
+    pub fn successors(&self, node: usize) -> &[usize] {
+        &self.successors[node]
+    }
+
+    pub fn predecessors(&self, node: usize) -> &[usize] {
+        &self.predecessors[node]
+    }
+
+    /// Returns for every node whether it can be reached from `start`.
+    pub fn reachable_from(&self, start: usize) -> Vec<bool> {
+        search(start, &self.successors)
+    }
+
+    /// Returns for every node whether `target` can be reached from it.
+    pub fn reaching(&self, target: usize) -> Vec<bool> {
+        search(target, &self.predecessors)
+    }
+}
+
+/// Returns the locations that control can be transferred to after executing the instruction at
+/// `index`, including locations outside of the program.
+pub fn targets(program: &[Instruction], index: usize) -> Vec<i64> {
+    instruction_targets(&program[index], index, program.len())
+}
+
+/// Returns the locations that control can be transferred to after executing `instruction` at
+/// `index` in a program of `program_len` instructions.
+pub fn instruction_targets(
+    instruction: &Instruction,
+    index: usize,
+    program_len: usize,
+) -> Vec<i64> {
+    let next = index as i64 + 1;
+    let jump = index as i64 + instruction.argument as i64;
+    match instruction.operation {
+        Operation::JMP => vec![jump],
+        Operation::JZ(_) | Operation::JNZ(_) | Operation::JGZ(_) => vec![next, jump],
+        // Halting is equivalent to jumping to the end of the program.
+        Operation::HLT => vec![program_len as i64],
+        _ => vec![next],
+    }
+}
+
+/// Splits a program into basic blocks: maximal ranges of instructions that can only be entered
+/// at their first instruction, and that only transfer control elsewhere at their last.
+pub fn basic_blocks(program: &[Instruction]) -> Vec<Range<usize>> {
+    let mut leaders: BTreeSet<usize> = BTreeSet::new();
+    leaders.insert(0);
+    for (index, instruction) in program.iter().enumerate() {
+        let branches = matches!(
+            instruction.operation,
+            Operation::JMP
+                | Operation::JZ(_)
+                | Operation::JNZ(_)
+                | Operation::JGZ(_)
+                | Operation::HLT
+        );
+        if branches {
+            leaders.insert(index + 1);
+            leaders.extend(
+                targets(program, index)
+                    .into_iter()
+                    .filter(|&target| target >= 0)
+                    .map(|target| target as usize),
+            );
+        }
+    }
+    let leaders: Vec<usize> = leaders
+        .into_iter()
+        .filter(|&leader| leader < program.len())
+        .chain(std::iter::once(program.len()))
+        .collect();
+    leaders
+        .windows(2)
+        .map(|window| window[0]..window[1])
+        .collect()
+}
+
+/// Breadth-first search from `start` over `edges`.
+fn search(start: usize, edges: &[Vec<usize>]) -> Vec<bool> {
+    let mut visited = vec![false; edges.len()];
+    let mut queue = VecDeque::new();
+    visited[start] = true;
+    queue.push_back(start);
+    while let Some(node) = queue.pop_front() {
+        for &next in &edges[node] {
+            if !visited[next] {
+                visited[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    visited
+}
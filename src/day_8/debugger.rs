@@ -0,0 +1,524 @@
+use super::{ExecutionOutcome, Instruction, Operation, Processor, ProcessorState, Register};
+use std::collections::{BTreeSet, VecDeque};
+use std::str::FromStr;
+
+/// The number of instructions that are shown before and after the program counter by `list`.
+const LIST_WINDOW: usize = 4;
+
+/// The number of processor states that are kept to step back through; older states are dropped.
+const MAX_HISTORY: usize = 10_000;
+
+/// A command that can be given to the debugger.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Command {
+    // Executes the next `n` instructions.
+    Step(usize),
+    // Undoes the last `n` instructions.
+    Back(usize),
+    // Runs until a breakpoint is hit, the watched accumulator changes or the program stops.
+    Continue,
+    // Sets a breakpoint on an instruction index.
+    Break(usize),
+    // Removes a breakpoint from an instruction index.
+    Delete(usize),
+    // Toggles whether execution stops when the accumulator changes.
+    Watch,
+    // Prints the instructions around the program counter.
+    List,
+    // Prints the program counter and all non-zero registers.
+    Registers,
+    // Replaces the instruction at an index, optionally checking the operation it replaces.
+    Patch {
+        index: usize,
+        expected: Option<Operation>,
+        replacement: Replacement,
+    },
+    // Resets the processor to its initial state.
+    Reset,
+    Help,
+    Quit,
+}
+
+/// What to replace an instruction with when patching.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Replacement {
+    // Replace the operation, but keep the argument.
+    Operation(Operation),
+    // Replace the entire instruction.
+    Instruction(Instruction),
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let count = |word: Option<&&str>| match word {
+            Some(n) => n
+                .parse::<usize>()
+                .map_err(|_| format!("Expected a number, but got '{}'", n)),
+            None => Ok(1),
+        };
+        let index = |word: Option<&&str>| match word {
+            Some(n) => n
+                .parse::<usize>()
+                .map_err(|_| format!("Expected an instruction index, but got '{}'", n)),
+            None => Err("Expected an instruction index".to_string()),
+        };
+        match words.first() {
+            Some(&"step") | Some(&"s") => Ok(Command::Step(count(words.get(1))?)),
+            Some(&"back") | Some(&"b") => Ok(Command::Back(count(words.get(1))?)),
+            Some(&"continue") | Some(&"c") => Ok(Command::Continue),
+            Some(&"break") => Ok(Command::Break(index(words.get(1))?)),
+            Some(&"delete") => Ok(Command::Delete(index(words.get(1))?)),
+            Some(&"watch") => Ok(Command::Watch),
+            Some(&"list") | Some(&"l") => Ok(Command::List),
+            Some(&"regs") => Ok(Command::Registers),
+            Some(&"patch") => {
+                let index = index(words.get(1))?;
+                let patch = words[2.min(words.len())..].join(" ");
+                parse_patch(index, &patch)
+            }
+            Some(&"reset") => Ok(Command::Reset),
+            Some(&"help") | Some(&"h") => Ok(Command::Help),
+            Some(&"quit") | Some(&"q") => Ok(Command::Quit),
+            Some(command) => Err(format!("Unknown command '{}', try 'help'", command)),
+            None => Err("Expected a command, try 'help'".to_string()),
+        }
+    }
+}
+
+/// Parses the patch of the `patch` command, which is either `from->to` (or `from→to`) where
+/// `from` and `to` are `acc`, `jmp` or `nop`, or a complete instruction.
+fn parse_patch(index: usize, patch: &str) -> Result<Command, String> {
+    let operation = |name: &str| match name {
+        "acc" => Ok(Operation::ACC),
+        "jmp" => Ok(Operation::JMP),
+        "nop" => Ok(Operation::NOP),
+        _ => Err(format!("Can only patch acc, jmp or nop, not '{}'", name)),
+    };
+    let arrow = patch
+        .find("->")
+        .map(|i| (i, 2))
+        .or_else(|| patch.find('→').map(|i| (i, '→'.len_utf8())));
+    match arrow {
+        Some((i, len)) => Ok(Command::Patch {
+            index,
+            expected: Some(operation(patch[..i].trim())?),
+            replacement: Replacement::Operation(operation(patch[i + len..].trim())?),
+        }),
+        None if patch.is_empty() => Err("Expected a patch such as 'jmp->nop'".to_string()),
+        None => Ok(Command::Patch {
+            index,
+            expected: None,
+            replacement: Replacement::Instruction(Instruction::from_str(patch)?),
+        }),
+    }
+}
+
+/// An interactive debugger for programs running on a `Processor`.
+pub struct Debugger {
+    processor: Processor,
+    breakpoints: BTreeSet<usize>,
+    watch_accumulator: bool,
+    // The states of the processor before the last `MAX_HISTORY` executed instructions, used to
+    // step back.
+    history: VecDeque<ProcessorState>,
+}
+
+impl Debugger {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Debugger {
+            processor: Processor::with_program(program),
+            breakpoints: BTreeSet::new(),
+            watch_accumulator: false,
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn processor(&self) -> &Processor {
+        &self.processor
+    }
+
+    /// Executes `command` and returns the text to show to the user.
+    pub fn execute(&mut self, command: &Command) -> Result<String, String> {
+        match command {
+            Command::Step(n) => Ok(self.step(*n)),
+            Command::Back(n) => Ok(self.back(*n)),
+            Command::Continue => Ok(self.resume()),
+            Command::Break(index) => {
+                self.check_index(*index)?;
+                self.breakpoints.insert(*index);
+                Ok(format!("Breakpoint set at {}", index))
+            }
+            Command::Delete(index) => {
+                if self.breakpoints.remove(index) {
+                    Ok(format!("Breakpoint at {} deleted", index))
+                } else {
+                    Err(format!("There is no breakpoint at {}", index))
+                }
+            }
+            Command::Watch => {
+                self.watch_accumulator = !self.watch_accumulator;
+                if self.watch_accumulator {
+                    Ok("Watching the accumulator".to_string())
+                } else {
+                    Ok("No longer watching the accumulator".to_string())
+                }
+            }
+            Command::List => Ok(self.list()),
+            Command::Registers => Ok(self.registers()),
+            Command::Patch {
+                index,
+                expected,
+                replacement,
+            } => self.patch(*index, expected, replacement),
+            Command::Reset => {
+                self.processor.reset();
+                self.history.clear();
+                Ok(self.location())
+            }
+            Command::Help => Ok(HELP.to_string()),
+            Command::Quit => Ok(String::new()),
+        }
+    }
+
+    /// Executes up to `n` instructions.
+    fn step(&mut self, n: usize) -> String {
+        let mut messages: Vec<String> = Vec::new();
+        for _ in 0..n {
+            let before = self.processor.snapshot();
+            if let Some(outcome) = self.processor.step() {
+                messages.push(describe(&outcome));
+                break;
+            }
+            record(&mut self.history, before);
+        }
+        messages.push(self.location());
+        messages.join("\n")
+    }
+
+    /// Undoes up to `n` instructions.
+    fn back(&mut self, n: usize) -> String {
+        for _ in 0..n {
+            match self.history.pop_back() {
+                Some(state) => self.processor.restore(&state),
+                None => {
+                    return format!("At the start of the recorded history\n{}", self.location())
+                }
+            }
+        }
+        self.location()
+    }
+
+    /// Runs until a breakpoint is hit, the accumulator changes while it is being watched, or the
+    /// program stops.
+    fn resume(&mut self) -> String {
+        let breakpoints = &self.breakpoints;
+        let watch_accumulator = self.watch_accumulator;
+        let history = &mut self.history;
+        let mut first = true;
+        let mut accumulator = self.processor.accumulator();
+        let mut reason: Option<String> = None;
+
+        let outcome = self.processor.run_until(|processor| {
+            // Breakpoints are checked before the instruction executes, except for the instruction
+            // the debugger is currently stopped at.
+            let pc = processor.program_counter();
+            if !first && pc >= 0 && breakpoints.contains(&(pc as usize)) {
+                reason = Some(format!("Hit breakpoint at {}", pc));
+                return true;
+            }
+            if watch_accumulator && processor.accumulator() != accumulator {
+                reason = Some(format!(
+                    "Accumulator changed from {} to {}",
+                    accumulator,
+                    processor.accumulator()
+                ));
+                return true;
+            }
+            first = false;
+            accumulator = processor.accumulator();
+            record(history, processor.snapshot());
+            false
+        });
+
+        if let Some(outcome) = outcome {
+            // The processor stopped before executing the instruction it was recorded for.
+            self.history.pop_back();
+            reason = Some(describe(&outcome));
+        }
+        format!("{}\n{}", reason.unwrap_or_default(), self.location())
+    }
+
+    /// Prints the instructions around the program counter.
+    fn list(&self) -> String {
+        let program = self.processor.program();
+        let pc = self.processor.program_counter();
+        let center = pc.max(0) as usize;
+        let start = center.saturating_sub(LIST_WINDOW);
+        let end = (center + LIST_WINDOW + 1).min(program.len());
+        let mut lines: Vec<String> = Vec::new();
+        for (index, instruction) in program.iter().enumerate().take(end).skip(start) {
+            let marker = if index as i64 == pc { "=>" } else { "  " };
+            let breakpoint = if self.breakpoints.contains(&index) {
+                '*'
+            } else {
+                ' '
+            };
+            lines.push(format!(
+                "{}{} {:>4}  {}",
+                marker, breakpoint, index, instruction
+            ));
+        }
+        if lines.is_empty() {
+            return self.location();
+        }
+        lines.join("\n")
+    }
+
+    /// Prints the program counter and all non-zero registers.
+    fn registers(&self) -> String {
+        let mut line = format!(
+            "pc={} acc={}",
+            self.processor.program_counter(),
+            self.processor.accumulator()
+        );
+        for name in b'a'..=b'z' {
+            let register = Register::from_name(&(name as char).to_string()).unwrap();
+            let value = self.processor.register(register);
+            if value != 0 {
+                line.push_str(&format!(" {}={}", register, value));
+            }
+        }
+        if !self.processor.output().is_empty() {
+            line.push_str(&format!(" out={:?}", self.processor.output()));
+        }
+        line
+    }
+
+    fn patch(
+        &mut self,
+        index: usize,
+        expected: &Option<Operation>,
+        replacement: &Replacement,
+    ) -> Result<String, String> {
+        self.check_index(index)?;
+        let current = self.processor.program()[index];
+        if let Some(expected) = expected {
+            if current.operation != *expected {
+                return Err(format!(
+                    "Instruction {} is '{}', expected an instruction of type {:?}",
+                    index, current, expected
+                ));
+            }
+        }
+        let instruction = match replacement {
+            Replacement::Operation(operation) => Instruction::new(*operation, current.argument),
+            Replacement::Instruction(instruction) => *instruction,
+        };
+        self.processor.patch(index, instruction);
+        Ok(format!(
+            "Patched {}: '{}' -> '{}'",
+            index, current, instruction
+        ))
+    }
+
+    fn check_index(&self, index: usize) -> Result<(), String> {
+        if index >= self.processor.program().len() {
+            return Err(format!(
+                "Instruction {} is out of bounds, the program has {} instructions",
+                index,
+                self.processor.program().len()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Describes the current location of the processor.
+    fn location(&self) -> String {
+        let pc = self.processor.program_counter();
+        let instruction = if pc >= 0 {
+            self.processor.program().get(pc as usize)
+        } else {
+            None
+        };
+        match instruction {
+            Some(instruction) => format!(
+                "=> {:>4}  {}  (acc={})",
+                pc,
+                instruction,
+                self.processor.accumulator()
+            ),
+            None => format!("=> {:>4}  (acc={})", pc, self.processor.accumulator()),
+        }
+    }
+}
+
+/// Describes why the processor stopped.
+/// Appends `state` to `history`, dropping the oldest state once `MAX_HISTORY` states are kept.
+fn record(history: &mut VecDeque<ProcessorState>, state: ProcessorState) {
+    if history.len() == MAX_HISTORY {
+        history.pop_front();
+    }
+    history.push_back(state);
+}
+
+fn describe(outcome: &ExecutionOutcome) -> String {
+    match outcome {
+        ExecutionOutcome::Terminated => "Program terminated".to_string(),
+        ExecutionOutcome::LoopDetected { instructions } => {
+            format!("Loop detected through instructions {:?}", instructions)
+        }
+        ExecutionOutcome::JumpedOutOfBounds { program_counter } => {
+            format!("Jumped out of bounds to {}", program_counter)
+        }
+        ExecutionOutcome::FuelExhausted => "Ran out of fuel".to_string(),
+        ExecutionOutcome::DivisionByZero { program_counter } => {
+            format!("Division by zero at {}", program_counter)
+        }
+    }
+}
+
+const HELP: &str = "Commands:
+  step [n], s [n]      execute the next n instructions
+  back [n], b [n]      undo the last n instructions
+  continue, c          run until a breakpoint, a watched change or the end of the program
+  break N              set a breakpoint at instruction N
+  delete N             remove the breakpoint at instruction N
+  watch                toggle stopping when the accumulator changes
+  list, l              show the instructions around the program counter
+  regs                 show the program counter and registers
+  patch N jmp->nop     replace the operation of instruction N, keeping its argument
+  patch N <instr>      replace instruction N, e.g. 'patch 7 nop -4'
+  reset                restart the program
+  quit, q              exit the debugger";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn debugger() -> Debugger {
+        let program = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+        Debugger::new(Instruction::from_reader(&mut BufReader::new(
+            program.as_bytes(),
+        )))
+    }
+
+    fn execute(debugger: &mut Debugger, command: &str) -> Result<String, String> {
+        debugger.execute(&Command::from_str(command)?)
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(Command::from_str("step"), Ok(Command::Step(1)));
+        assert_eq!(Command::from_str("s 5"), Ok(Command::Step(5)));
+        assert_eq!(
+            Command::from_str("patch 7 jmp→nop"),
+            Ok(Command::Patch {
+                index: 7,
+                expected: Some(Operation::JMP),
+                replacement: Replacement::Operation(Operation::NOP)
+            })
+        );
+        assert_eq!(
+            Command::from_str("patch 7 nop -4"),
+            Ok(Command::Patch {
+                index: 7,
+                expected: None,
+                replacement: Replacement::Instruction(Instruction::new(Operation::NOP, -4))
+            })
+        );
+        assert!(Command::from_str("patch 7 jmp->add").is_err());
+        assert!(Command::from_str("break").is_err());
+        assert!(Command::from_str("jump").is_err());
+    }
+
+    #[test]
+    fn test_step_and_back() {
+        let mut debugger = debugger();
+        execute(&mut debugger, "step 3").unwrap();
+        assert_eq!(debugger.processor().program_counter(), 6);
+        assert_eq!(debugger.processor().accumulator(), 1);
+
+        execute(&mut debugger, "back 2").unwrap();
+        assert_eq!(debugger.processor().program_counter(), 1);
+        assert_eq!(debugger.processor().accumulator(), 0);
+
+        let output = execute(&mut debugger, "back 5").unwrap();
+        assert!(output.starts_with("At the start of the recorded history"));
+        assert_eq!(debugger.processor().program_counter(), 0);
+
+        assert_eq!(
+            execute(&mut debugger, "list").unwrap(),
+            [
+                "=>     0  nop +0",
+                "       1  acc +1",
+                "       2  jmp +4",
+                "       3  acc +3",
+                "       4  jmp -3",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut debugger = debugger();
+        execute(&mut debugger, &format!("step {}", MAX_HISTORY + 10)).unwrap();
+        assert_eq!(debugger.history.len(), MAX_HISTORY);
+
+        let output = execute(&mut debugger, &format!("back {}", MAX_HISTORY + 10)).unwrap();
+        assert!(output.starts_with("At the start of the recorded history"));
+        assert!(debugger.history.is_empty());
+    }
+
+    #[test]
+    fn test_continue() {
+        let mut debugger = debugger();
+        execute(&mut debugger, "break 3").unwrap();
+        let output = execute(&mut debugger, "c").unwrap();
+        assert_eq!(output, "Hit breakpoint at 3\n=>    3  acc +3  (acc=2)");
+
+        execute(&mut debugger, "delete 3").unwrap();
+        execute(&mut debugger, "watch").unwrap();
+        let output = execute(&mut debugger, "c").unwrap();
+        assert_eq!(
+            output,
+            "Accumulator changed from 2 to 5\n=>    4  jmp -3  (acc=5)"
+        );
+
+        execute(&mut debugger, "watch").unwrap();
+        let output = execute(&mut debugger, "c").unwrap();
+        assert!(output.starts_with("Loop detected"));
+        assert_eq!(debugger.processor().program_counter(), 4);
+        assert_eq!(debugger.processor().accumulator(), 10);
+
+        // Every executed instruction can be undone.
+        execute(&mut debugger, "back 100").unwrap();
+        assert_eq!(debugger.processor().program_counter(), 0);
+    }
+
+    #[test]
+    fn test_patch() {
+        let mut debugger = debugger();
+        assert!(execute(&mut debugger, "patch 7 nop->jmp").is_err());
+        assert!(execute(&mut debugger, "patch 70 jmp->nop").is_err());
+        assert_eq!(
+            execute(&mut debugger, "patch 7 jmp->nop"),
+            Ok("Patched 7: 'jmp -4' -> 'nop -4'".to_string())
+        );
+        let output = execute(&mut debugger, "c").unwrap();
+        assert_eq!(output, "Program terminated\n=>    9  (acc=8)");
+        assert_eq!(execute(&mut debugger, "regs"), Ok("pc=9 acc=8".to_string()));
+    }
+}
@@ -0,0 +1,270 @@
+use super::{basic_blocks, targets, ControlFlowGraph, Instruction, Operation};
+use std::fmt;
+use std::ops::Range;
+
+/// A problem that was found in a program without running it.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LintKind {
+    // The instruction can never be executed.
+    Unreachable,
+    // The instruction jumps to a location outside of the program, other than directly after it.
+    OutOfRangeJump { target: i64 },
+    // A `jmp +0`, which loops forever.
+    SelfLoop,
+    // A `nop` with a non-zero argument that would never terminate if it were flipped to a `jmp`.
+    NopWouldLoop { target: usize },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Lint {
+    // The index of the instruction the lint applies to.
+    pub index: usize,
+    pub kind: LintKind,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LintKind::Unreachable => write!(f, "{}: instruction is unreachable", self.index),
+            LintKind::OutOfRangeJump { target } => {
+                write!(f, "{}: jump to out-of-range target {}", self.index, target)
+            }
+            LintKind::SelfLoop => write!(f, "{}: jmp +0 loops forever", self.index),
+            LintKind::NopWouldLoop { target } => write!(
+                f,
+                "{}: flipping this nop to a jmp to {} would loop forever",
+                self.index, target
+            ),
+        }
+    }
+}
+
+/// The result of linting a program.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LintReport {
+    // The lints, ordered by instruction index.
+    pub lints: Vec<Lint>,
+    pub basic_blocks: Vec<Range<usize>>,
+}
+
+/// Analyzes a program without running it.
+///
+/// Whether a flipped `nop` would loop is decided on the control-flow graph. For programs with
+/// conditional jumps, a `nop` is only reported if no path leads from its target to termination.
+pub fn lint(program: &[Instruction]) -> LintReport {
+    let graph = ControlFlowGraph::from_program(program);
+    let reachable = graph.reachable_from(0);
+    let terminates = graph.reaching(graph.exit());
+    let conditional = program.iter().any(|i| {
+        matches!(
+            i.operation,
+            Operation::JZ(_) | Operation::JNZ(_) | Operation::JGZ(_)
+        )
+    });
+    let passes_through = if conditional {
+        None
+    } else {
+        Some(PathTree::new(&graph, &terminates))
+    };
+
+    let mut lints: Vec<Lint> = Vec::new();
+    for (index, instruction) in program.iter().enumerate() {
+        let mut push = |kind: LintKind| lints.push(Lint { index, kind });
+        if !reachable[index] {
+            push(LintKind::Unreachable);
+        }
+        if is_jump(instruction) {
+            for target in targets(program, index) {
+                if target < 0 || target as usize > program.len() {
+                    push(LintKind::OutOfRangeJump { target });
+                }
+            }
+        }
+        match instruction.operation {
+            Operation::JMP if instruction.argument == 0 => push(LintKind::SelfLoop),
+            Operation::NOP if instruction.argument != 0 => {
+                let target = index as i64 + instruction.argument as i64;
+                if target < 0 || target as usize > program.len() {
+                    continue;
+                }
+                let target = target as usize;
+                // If the only path from the target to termination passes through the nop, then
+                // the flipped nop jumps back to the target instead.
+                let loops = !terminates[target]
+                    || passes_through
+                        .as_ref()
+                        .is_some_and(|tree| tree.passes_through(target, index));
+                if loops {
+                    push(LintKind::NopWouldLoop { target });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    LintReport {
+        lints,
+        basic_blocks: basic_blocks(program),
+    }
+}
+
+fn is_jump(instruction: &Instruction) -> bool {
+    matches!(
+        instruction.operation,
+        Operation::JMP | Operation::JZ(_) | Operation::JNZ(_) | Operation::JGZ(_)
+    )
+}
+
+/// In a program without conditional jumps, every instruction has at most one successor, so the
+/// instructions that terminate form a tree rooted at the exit. The path from an instruction to
+/// the exit passes through another instruction if and only if it lies in its subtree.
+struct PathTree {
+    // The order in which every node is entered and left in a depth-first traversal of the tree.
+    entered: Vec<usize>,
+    left: Vec<usize>,
+}
+
+impl PathTree {
+    fn new(graph: &ControlFlowGraph, terminates: &[bool]) -> Self {
+        let mut entered = vec![0; terminates.len()];
+        let mut left = vec![0; terminates.len()];
+        let mut time = 0;
+        // Contains the node, and whether its children have been visited.
+        let mut stack = vec![(graph.exit(), false)];
+        while let Some((node, visited)) = stack.pop() {
+            time += 1;
+            if visited {
+                left[node] = time;
+                continue;
+            }
+            entered[node] = time;
+            stack.push((node, true));
+            for &child in graph.predecessors(node) {
+                if terminates[child] {
+                    stack.push((child, false));
+                }
+            }
+        }
+        PathTree { entered, left }
+    }
+
+    /// Returns whether the path from `from` to the exit passes through `through`.
+    fn passes_through(&self, from: usize, through: usize) -> bool {
+        self.entered[through] <= self.entered[from] && self.left[from] <= self.left[through]
+    }
+}
+
+/// Renders the control-flow graph of a program as a Graphviz DOT digraph with a node for every
+/// basic block. Unreachable blocks are dashed, and jumps to out-of-range targets lead to a red
+/// `fault` node.
+pub fn cfg_to_dot(program: &[Instruction]) -> String {
+    let blocks = basic_blocks(program);
+    let reachable = ControlFlowGraph::from_program(program).reachable_from(0);
+    let node = |target: i64| {
+        if target == program.len() as i64 {
+            "exit".to_string()
+        } else if target < 0 || target > program.len() as i64 {
+            "fault".to_string()
+        } else {
+            format!("b{}", target)
+        }
+    };
+
+    let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+    let mut faults = false;
+    for block in blocks.iter() {
+        let label: String = program[block.clone()]
+            .iter()
+            .enumerate()
+            .map(|(offset, instruction)| format!("{}: {}\\l", block.start + offset, instruction))
+            .collect();
+        let style = if reachable[block.start] {
+            ""
+        } else {
+            ", style=dashed"
+        };
+        dot.push_str(&format!(
+            "    b{} [label=\"{}\"{}];\n",
+            block.start, label, style
+        ));
+
+        let last = block.end - 1;
+        for target in targets(program, last) {
+            faults |= node(target) == "fault";
+            dot.push_str(&format!("    b{} -> {};\n", block.start, node(target)));
+        }
+    }
+    dot.push_str("    exit [shape=doublecircle];\n");
+    if faults {
+        dot.push_str("    fault [shape=octagon, color=red];\n");
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    fn program(source: &str) -> Vec<Instruction> {
+        Instruction::from_reader(&mut BufReader::new(source.as_bytes()))
+    }
+
+    #[test]
+    fn test_lint() {
+        let instructions =
+            program("nop +3\nacc +1\njmp +2\njmp +0\nnop -3\njmp +3\nacc +5\njmp -10\nacc +1");
+        let report = lint(&instructions);
+        let lints: Vec<String> = report.lints.iter().map(|lint| lint.to_string()).collect();
+        assert_eq!(
+            lints,
+            vec![
+                "0: flipping this nop to a jmp to 3 would loop forever",
+                "3: instruction is unreachable",
+                "3: jmp +0 loops forever",
+                "4: flipping this nop to a jmp to 1 would loop forever",
+                "6: instruction is unreachable",
+                "7: instruction is unreachable",
+                "7: jump to out-of-range target -3",
+            ]
+        );
+        assert_eq!(report.basic_blocks, vec![0..3, 3..4, 4..6, 6..8, 8..9]);
+    }
+
+    #[test]
+    fn test_nop_would_loop() {
+        // Flipping the nop jumps back to the start, which is only a loop because the path from
+        // the start to the end of the program passes through the nop.
+        let instructions = program("acc +1\nnop -1\nacc +1");
+        assert_eq!(
+            lint(&instructions).lints,
+            vec![Lint {
+                index: 1,
+                kind: LintKind::NopWouldLoop { target: 0 }
+            }]
+        );
+        assert!(lint(&program("nop +2\nacc +1\nacc +1")).lints.is_empty());
+    }
+
+    #[test]
+    fn test_cfg_to_dot() {
+        let instructions = program("acc +1\njnz acc +2\njmp -5\nhlt");
+        assert_eq!(
+            cfg_to_dot(&instructions),
+            "digraph cfg {
+    node [shape=box, fontname=monospace];
+    b0 [label=\"0: acc +1\\l1: jnz acc +2\\l\"];
+    b0 -> b2;
+    b0 -> b3;
+    b2 [label=\"2: jmp -5\\l\"];
+    b2 -> fault;
+    b3 [label=\"3: hlt\\l\"];
+    b3 -> exit;
+    exit [shape=doublecircle];
+    fault [shape=octagon, color=red];
+}
+"
+        );
+    }
+}
@@ -0,0 +1,527 @@
+use super::{
+    instruction_targets, ControlFlowGraph, ExecutionOutcome, Instruction, Operation, Processor,
+    NUM_REGISTERS,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// A single corrupted instruction, and how to repair it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Repair {
+    // The index of the corrupted instruction.
+    pub index: usize,
+    pub old: Operation,
+    pub new: Operation,
+    // The value of the accumulator after the repaired program terminated.
+    pub accumulator: i64,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:?} -> {:?}, accumulator {}",
+            self.index, self.old, self.new, self.accumulator
+        )
+    }
+}
+
+/// Finds the single `jmp` or `nop` instruction that has to be flipped in order for the program
+/// to terminate, in time linear in the length of the program.
+///
+/// Flipping an instruction that is never executed does not change the behaviour of the program,
+/// so the corrupted instruction lies on the path the original program takes. As the original
+/// program loops, none of the instructions on that path can reach termination, so the first
+/// instruction on the path whose flipped successor can reach termination is the fix.
+pub fn find_fix(instructions: &[Instruction]) -> Result<Repair, String> {
+    if let Some(index) = instructions.iter().position(|i| {
+        matches!(
+            i.operation,
+            Operation::JZ(_) | Operation::JNZ(_) | Operation::JGZ(_)
+        )
+    }) {
+        return Err(format!(
+            "Instruction {} is a conditional jump, which is not supported",
+            index
+        ));
+    }
+
+    let graph = ControlFlowGraph::from_program(instructions);
+    let terminates = graph.reaching(graph.exit());
+    if terminates[0] {
+        return Err("The program already terminates".to_string());
+    }
+    let leads_to_exit =
+        |target: i64| target >= 0 && target as usize <= graph.exit() && terminates[target as usize];
+
+    // Walk the path of the original program.
+    let mut visited = vec![false; instructions.len()];
+    let mut pc = 0;
+    while pc < instructions.len() && !visited[pc] {
+        visited[pc] = true;
+        let instruction = instructions[pc];
+        let next = pc as i64 + 1;
+        let jump = pc as i64 + instruction.argument as i64;
+        let flipped = match instruction.operation {
+            Operation::JMP if leads_to_exit(next) => Some(Operation::NOP),
+            Operation::NOP if leads_to_exit(jump) => Some(Operation::JMP),
+            _ => None,
+        };
+        if let Some(new) = flipped {
+            return repair(instructions, pc, new);
+        }
+        match graph.successors(pc).first() {
+            Some(&successor) => pc = successor,
+            None => break,
+        }
+    }
+    Err("No single jmp or nop instruction could be flipped to terminate the program".to_string())
+}
+
+/// Applies a repair to the program and runs it to determine the final accumulator.
+fn repair(instructions: &[Instruction], index: usize, new: Operation) -> Result<Repair, String> {
+    let old = instructions[index].operation;
+    let mut program = instructions.to_vec();
+    program[index] = Instruction::new(new, instructions[index].argument);
+
+    let mut processor = Processor::with_program(program);
+    match processor.run() {
+        ExecutionOutcome::Terminated => Ok(Repair {
+            index,
+            old,
+            new,
+            accumulator: processor.accumulator(),
+        }),
+        outcome => Err(format!(
+            "Flipping instruction {} did not terminate the program: {:?}",
+            index, outcome
+        )),
+    }
+}
+
+/// Configures which repairs `find_repairs` considers.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RepairOptions {
+    // The maximum number of instructions that may be edited.
+    pub max_edits: usize,
+    // Whether the sign of an argument may be flipped, e.g. `jmp +3` to `jmp -3`.
+    pub flip_signs: bool,
+    // Whether an `acc` instruction may be replaced by a `nop`.
+    pub delete_acc: bool,
+    // The maximum number of instructions to execute along a single candidate repair.
+    pub max_steps: usize,
+}
+
+impl Default for RepairOptions {
+    fn default() -> Self {
+        RepairOptions {
+            max_edits: 1,
+            flip_signs: false,
+            delete_acc: false,
+            max_steps: 1_000_000,
+        }
+    }
+}
+
+/// A change to a single instruction.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Edit {
+    pub index: usize,
+    pub old: Instruction,
+    pub new: Instruction,
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: '{}' -> '{}'", self.index, self.old, self.new)
+    }
+}
+
+/// A set of edits that makes a program terminate.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MultiRepair {
+    // The edits, ordered by instruction index.
+    pub edits: Vec<Edit>,
+    // The value of the accumulator after the repaired program terminated.
+    pub accumulator: i64,
+}
+
+/// Finds all minimal sets of at most `options.max_edits` edits that make the program terminate.
+///
+/// Only the fewest number of edits that works is reported, so an empty list means that no
+/// repair with at most `options.max_edits` edits exists, and a program that already terminates
+/// has a single repair without edits.
+///
+/// An edit only changes the behaviour of a program if the edited instruction is executed, so
+/// edits are only tried on instructions as the search executes them. Executions that can no
+/// longer reach termination with the remaining number of edits are pruned, where the number of
+/// edits that is needed is bounded from below by a shortest path search on the control-flow
+/// graph.
+pub fn find_repairs(program: &[Instruction], options: &RepairOptions) -> Vec<MultiRepair> {
+    let mut search = Search::new(program, options);
+    for budget in 0..=options.max_edits {
+        search.explore(budget, 0);
+        if !search.repairs.is_empty() {
+            break;
+        }
+    }
+    let mut repairs = search.repairs;
+    repairs.sort_by_key(|repair| repair.edits.iter().map(|e| e.index).collect::<Vec<_>>());
+    repairs
+}
+
+/// Returns the instructions an instruction may be edited into.
+fn candidates(instruction: &Instruction, options: &RepairOptions) -> Vec<Instruction> {
+    let argument = instruction.argument;
+    let mut candidates = Vec::new();
+    match instruction.operation {
+        Operation::JMP => candidates.push(Instruction::new(Operation::NOP, argument)),
+        Operation::NOP => candidates.push(Instruction::new(Operation::JMP, argument)),
+        Operation::ACC if options.delete_acc => {
+            candidates.push(Instruction::new(Operation::NOP, argument))
+        }
+        _ => {}
+    }
+    // The argument of a `nop` or `hlt` has no effect.
+    let ignores_argument = matches!(instruction.operation, Operation::NOP | Operation::HLT);
+    if options.flip_signs && argument != 0 && argument != i32::MIN && !ignores_argument {
+        candidates.push(Instruction::new(instruction.operation, -argument));
+    }
+    candidates
+}
+
+/// A location, and the state of the registers for conditional programs.
+type VisitKey = (usize, Option<[i64; NUM_REGISTERS]>);
+
+/// The state of a depth-first search over partial repairs.
+struct Search<'a> {
+    program: &'a [Instruction],
+    options: &'a RepairOptions,
+    // A lower bound on the number of edits that are needed to terminate from every location.
+    distance: Vec<usize>,
+    // Whether the control flow of the program depends on its registers.
+    conditional: bool,
+    processor: Processor,
+    // The locations (and states, for conditional programs) that were visited along the current
+    // execution, used to detect loops.
+    visited: HashSet<VisitKey>,
+    // Whether it was decided whether to edit the instruction at every location.
+    decided: Vec<bool>,
+    edits: Vec<Edit>,
+    // What was added to `visited` and `decided`, in order, so that it can be undone when the
+    // search backtracks.
+    visited_log: Vec<VisitKey>,
+    decided_log: Vec<usize>,
+    repairs: Vec<MultiRepair>,
+}
+
+impl<'a> Search<'a> {
+    fn new(program: &'a [Instruction], options: &'a RepairOptions) -> Self {
+        let conditional = program.iter().any(|i| {
+            matches!(
+                i.operation,
+                Operation::JZ(_) | Operation::JNZ(_) | Operation::JGZ(_)
+            )
+        });
+        Search {
+            program,
+            options,
+            distance: edit_distances(program, options),
+            conditional,
+            processor: Processor::new(),
+            visited: HashSet::new(),
+            decided: vec![false; program.len()],
+            edits: Vec::new(),
+            visited_log: Vec::new(),
+            decided_log: Vec::new(),
+            repairs: Vec::new(),
+        }
+    }
+
+    /// Executes the program from the current state of the processor, and branches into every
+    /// edit of an instruction the first time it is executed. Everything is restored to the state
+    /// it had before returning.
+    fn explore(&mut self, budget: usize, mut steps: usize) {
+        let state = self.processor.snapshot();
+        let visited_len = self.visited_log.len();
+        let decided_len = self.decided_log.len();
+        loop {
+            let pc = self.processor.program_counter;
+            if self.processor.halted || pc as usize == self.program.len() {
+                let mut edits = self.edits.clone();
+                edits.sort_by_key(|edit| edit.index);
+                self.repairs.push(MultiRepair {
+                    edits,
+                    accumulator: self.processor.accumulator(),
+                });
+                break;
+            }
+            if pc < 0 || pc as usize > self.program.len() {
+                break;
+            }
+            let pc = pc as usize;
+            if self.distance[pc] > budget || steps >= self.options.max_steps {
+                break;
+            }
+            let key = if self.conditional {
+                (pc, Some(self.processor.registers))
+            } else {
+                (pc, None)
+            };
+            if !self.visited.insert(key) {
+                break;
+            }
+            self.visited_log.push(key);
+
+            let original = self.program[pc];
+            let instruction = if self.decided[pc] {
+                self.edits
+                    .iter()
+                    .find(|edit| edit.index == pc)
+                    .map_or(original, |edit| edit.new)
+            } else {
+                self.decided[pc] = true;
+                self.decided_log.push(pc);
+                if budget > 0 {
+                    for candidate in candidates(&original, self.options) {
+                        self.edits.push(Edit {
+                            index: pc,
+                            old: original,
+                            new: candidate,
+                        });
+                        let before = self.processor.snapshot();
+                        if self.processor.execute_instruction(&candidate).is_none() {
+                            self.explore(budget - 1, steps + 1);
+                        }
+                        self.processor.restore(&before);
+                        self.edits.pop();
+                    }
+                }
+                original
+            };
+            if self.processor.execute_instruction(&instruction).is_some() {
+                break;
+            }
+            steps += 1;
+        }
+
+        for key in self.visited_log.drain(visited_len..) {
+            self.visited.remove(&key);
+        }
+        for pc in self.decided_log.drain(decided_len..) {
+            self.decided[pc] = false;
+        }
+        self.processor.restore(&state);
+    }
+}
+
+/// Computes for every location a lower bound on the number of edits that are needed to reach
+/// termination from it, using a 0-1 breadth-first search backwards from the exit. Following an
+/// instruction costs nothing, and following an edited instruction costs one edit.
+fn edit_distances(program: &[Instruction], options: &RepairOptions) -> Vec<usize> {
+    let exit = program.len();
+    let mut predecessors: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+    for (index, instruction) in program.iter().enumerate() {
+        let mut add = |instruction: &Instruction, cost: usize| {
+            for target in instruction_targets(instruction, index, exit) {
+                if target >= 0 && target as usize <= exit {
+                    predecessors
+                        .entry(target as usize)
+                        .or_default()
+                        .push((index, cost));
+                }
+            }
+        };
+        add(instruction, 0);
+        for candidate in candidates(instruction, options) {
+            add(&candidate, 1);
+        }
+    }
+
+    let mut distance = vec![usize::MAX; exit + 1];
+    let mut queue = VecDeque::new();
+    distance[exit] = 0;
+    queue.push_back(exit);
+    while let Some(node) = queue.pop_front() {
+        for &(predecessor, cost) in predecessors.get(&node).into_iter().flatten() {
+            let d = distance[node] + cost;
+            if d < distance[predecessor] {
+                distance[predecessor] = d;
+                if cost == 0 {
+                    queue.push_front(predecessor);
+                } else {
+                    queue.push_back(predecessor);
+                }
+            }
+        }
+    }
+    distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+    use std::str::FromStr;
+
+    fn program(source: &str) -> Vec<Instruction> {
+        Instruction::from_reader(&mut BufReader::new(source.as_bytes()))
+    }
+
+    #[test]
+    fn test_find_fix() {
+        // The example program, with the corrupted instruction flipped from jmp to nop.
+        let instructions =
+            program("nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6");
+        assert_eq!(
+            find_fix(&instructions),
+            Ok(Repair {
+                index: 7,
+                old: Operation::JMP,
+                new: Operation::NOP,
+                accumulator: 8
+            })
+        );
+
+        // A nop that has to become a jmp.
+        let instructions = program("nop +3\njmp +0\nacc +1\nacc +2");
+        assert_eq!(
+            find_fix(&instructions),
+            Ok(Repair {
+                index: 0,
+                old: Operation::NOP,
+                new: Operation::JMP,
+                accumulator: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_find_fix_errors() {
+        assert_eq!(
+            find_fix(&program("acc +1\nnop +0")),
+            Err("The program already terminates".to_string())
+        );
+        assert!(find_fix(&program("jmp +0\njmp -1")).is_err());
+        assert!(find_fix(&[Instruction::from_str("jnz a -1").unwrap()]).is_err());
+    }
+
+    #[test]
+    fn test_control_flow_graph() {
+        let instructions = program("nop +0\njmp +2\nacc +1\njmp -3\nhlt\njmp +7");
+        let graph = ControlFlowGraph::from_program(&instructions);
+        assert_eq!(graph.exit(), 6);
+        assert_eq!(graph.successors(1), &[3]);
+        assert_eq!(graph.successors(4), &[6]);
+        assert!(graph.successors(5).is_empty());
+        assert_eq!(graph.predecessors(0), &[3]);
+        assert_eq!(
+            graph.reachable_from(0),
+            vec![true, true, false, true, false, false, false]
+        );
+        assert_eq!(
+            graph.reaching(graph.exit()),
+            vec![false, false, false, false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_find_repairs() {
+        let instructions =
+            program("nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6");
+        let repairs = find_repairs(&instructions, &RepairOptions::default());
+        assert_eq!(
+            repairs,
+            vec![MultiRepair {
+                edits: vec![Edit {
+                    index: 7,
+                    old: Instruction::new(Operation::JMP, -4),
+                    new: Instruction::new(Operation::NOP, -4)
+                }],
+                accumulator: 8
+            }]
+        );
+
+        // A program that terminates needs no edits.
+        let repairs = find_repairs(&program("acc +2\nnop +0"), &RepairOptions::default());
+        assert_eq!(
+            repairs,
+            vec![MultiRepair {
+                edits: vec![],
+                accumulator: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_repairs_multiple_edits() {
+        // Two corrupted instructions, the second of which is only executed once the first has
+        // been repaired.
+        let instructions = program("jmp +2\nacc +1\njmp -1\njmp -3\nacc +4");
+        let options = RepairOptions::default();
+        assert!(find_repairs(&instructions, &options).is_empty());
+
+        let options = RepairOptions {
+            max_edits: 2,
+            ..RepairOptions::default()
+        };
+        let repairs = find_repairs(&instructions, &options);
+        let edits: Vec<String> = repairs
+            .iter()
+            .flat_map(|r| r.edits.iter().map(|e| e.to_string()))
+            .collect();
+        assert_eq!(
+            edits,
+            vec!["2: 'jmp -1' -> 'nop -1'", "3: 'jmp -3' -> 'nop -3'"]
+        );
+        assert_eq!(repairs[0].accumulator, 4);
+
+        // Only flipping the sign of the last jump repairs the program with a single edit.
+        let instructions = program("acc +1\njmp +2\njmp +0\njmp -2\njmp +0\nacc +2");
+        let options = RepairOptions {
+            flip_signs: true,
+            ..RepairOptions::default()
+        };
+        let repairs = find_repairs(&instructions, &options);
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].edits[0].to_string(), "3: 'jmp -2' -> 'jmp +2'");
+        assert_eq!(repairs[0].accumulator, 3);
+    }
+
+    #[test]
+    fn test_find_repairs_delete_acc() {
+        // The conditional jump loops forever unless the accumulator is zero.
+        let instructions = program("acc +1\njnz acc +0\nacc +5");
+        assert!(find_repairs(&instructions, &RepairOptions::default()).is_empty());
+
+        let options = RepairOptions {
+            delete_acc: true,
+            ..RepairOptions::default()
+        };
+        let repairs = find_repairs(&instructions, &options);
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].edits[0].to_string(), "0: 'acc +1' -> 'nop +1'");
+        assert_eq!(repairs[0].accumulator, 5);
+    }
+
+    #[test]
+    fn test_find_repairs_large_program() {
+        // A long chain of jumps that each skip one instruction, with a single corrupted jump
+        // halfway that loops back to the start.
+        let mut instructions = Vec::new();
+        for _ in 0..5000 {
+            instructions.push(Instruction::new(Operation::JMP, 2));
+            instructions.push(Instruction::new(Operation::ACC, 1));
+        }
+        instructions[5000] = Instruction::new(Operation::JMP, -5000);
+        instructions[5001] = Instruction::new(Operation::ACC, 7);
+        let options = RepairOptions {
+            max_edits: 2,
+            ..RepairOptions::default()
+        };
+        let repairs = find_repairs(&instructions, &options);
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].edits[0].index, 5000);
+        assert_eq!(repairs[0].accumulator, 7);
+    }
+}
@@ -0,0 +1,249 @@
+use super::{basic_blocks, ExecutionOutcome, Instruction};
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// Determines what a processor records while it executes instructions.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum TraceMode {
+    // Nothing is recorded.
+    Off,
+    // Only the number of times every instruction was executed is recorded.
+    Profile,
+    // Every executed instruction is recorded, as well as the hit counts.
+    Full,
+}
+
+/// An executed instruction.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TraceEntry {
+    pub program_counter: usize,
+    pub instruction: Instruction,
+    pub accumulator_before: i64,
+    pub accumulator_after: i64,
+}
+
+/// What a processor recorded while executing a program.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Trace {
+    // The executed instructions, in order. Only recorded in `TraceMode::Full`.
+    entries: Vec<TraceEntry>,
+    // The number of times every instruction was executed.
+    hits: Vec<u64>,
+    full: bool,
+}
+
+impl Trace {
+    pub fn new(mode: TraceMode, program_len: usize) -> Option<Self> {
+        match mode {
+            TraceMode::Off => None,
+            _ => Some(Trace {
+                entries: Vec::new(),
+                hits: vec![0; program_len],
+                full: mode == TraceMode::Full,
+            }),
+        }
+    }
+
+    pub fn record(&mut self, entry: TraceEntry) {
+        self.hits[entry.program_counter] += 1;
+        if self.full {
+            self.entries.push(entry);
+        }
+    }
+
+    /// Discards everything that was recorded.
+    pub fn clear(&mut self, program_len: usize) {
+        self.entries.clear();
+        self.hits = vec![0; program_len];
+    }
+
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    pub fn hits(&self) -> &[u64] {
+        &self.hits
+    }
+
+    /// Writes the trace as aligned text, one executed instruction per line.
+    pub fn write_text<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{:>6}  {:<12} acc {} -> {}",
+                entry.program_counter,
+                entry.instruction.to_string(),
+                entry.accumulator_before,
+                entry.accumulator_after
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the trace as CSV with the columns `pc`, `op`, `argument`, `acc_before` and
+    /// `acc_after`.
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "pc,op,argument,acc_before,acc_after")?;
+        for entry in &self.entries {
+            let text = entry.instruction.to_string();
+            let op = text.split_whitespace().next().unwrap_or_default();
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                entry.program_counter,
+                op,
+                entry.instruction.argument(),
+                entry.accumulator_before,
+                entry.accumulator_after
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// How often a basic block was executed.
+#[derive(Debug, PartialEq, Clone)]
+pub struct BlockProfile {
+    pub block: Range<usize>,
+    // The number of times the block was entered.
+    pub entries: u64,
+    // The total number of instructions that were executed in the block.
+    pub executed: u64,
+}
+
+/// A summary of where a program spent its time.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Profile {
+    pub hits: Vec<u64>,
+    // The basic blocks that were executed, the hottest first.
+    pub hottest_blocks: Vec<BlockProfile>,
+    // The instructions of the loop the program was stuck in, if any.
+    pub loop_body: Option<Vec<usize>>,
+}
+
+impl Profile {
+    pub fn new(program: &[Instruction], trace: &Trace, outcome: &ExecutionOutcome) -> Self {
+        let hits = trace.hits().to_vec();
+        let mut hottest_blocks: Vec<BlockProfile> = basic_blocks(program)
+            .into_iter()
+            .map(|block| BlockProfile {
+                entries: hits[block.start],
+                executed: hits[block.clone()].iter().sum(),
+                block,
+            })
+            .filter(|profile| profile.executed > 0)
+            .collect();
+        hottest_blocks.sort_by(|a, b| {
+            b.executed
+                .cmp(&a.executed)
+                .then(a.block.start.cmp(&b.block.start))
+        });
+        let loop_body = match outcome {
+            ExecutionOutcome::LoopDetected { instructions } => Some(instructions.clone()),
+            _ => None,
+        };
+        Profile {
+            hits,
+            hottest_blocks,
+            loop_body,
+        }
+    }
+
+    /// Writes a human readable summary of at most `limit` of the hottest blocks.
+    pub fn write_summary<W: Write>(&self, writer: &mut W, limit: usize) -> io::Result<()> {
+        let executed: u64 = self.hits.iter().sum();
+        let visited = self.hits.iter().filter(|&&hits| hits > 0).count();
+        writeln!(
+            writer,
+            "{} instructions executed, {} of {} instructions visited",
+            executed,
+            visited,
+            self.hits.len()
+        )?;
+        writeln!(writer, "Hottest basic blocks:")?;
+        for profile in self.hottest_blocks.iter().take(limit) {
+            writeln!(
+                writer,
+                "  {:>5}..{:<5} entered {} times, {} instructions executed",
+                profile.block.start, profile.block.end, profile.entries, profile.executed
+            )?;
+        }
+        if let Some(body) = &self.loop_body {
+            let body: Vec<String> = body.iter().map(|index| index.to_string()).collect();
+            writeln!(writer, "Loop body: {}", body.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_8::Processor;
+    use std::io::BufReader;
+
+    fn example_processor(mode: TraceMode) -> Processor {
+        let source = "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6";
+        let mut processor = Processor::with_program(Instruction::from_reader(&mut BufReader::new(
+            source.as_bytes(),
+        )));
+        processor.set_trace_mode(mode);
+        processor
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut processor = example_processor(TraceMode::Full);
+        processor.run();
+        let trace = processor.trace().unwrap();
+        assert_eq!(trace.entries().len(), 7);
+        assert_eq!(trace.hits(), &[1, 1, 1, 1, 1, 0, 1, 1, 0]);
+
+        let mut csv = Vec::new();
+        trace.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "pc,op,argument,acc_before,acc_after");
+        assert_eq!(lines[2], "1,acc,1,0,1");
+        assert_eq!(lines[7], "4,jmp,-3,5,5");
+
+        let mut text = Vec::new();
+        trace.write_text(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert_eq!(text.lines().nth(3), Some("     6  acc +1       acc 1 -> 2"));
+
+        // Resetting discards the trace.
+        processor.reset();
+        assert!(processor.trace().unwrap().entries().is_empty());
+
+        let mut processor = example_processor(TraceMode::Off);
+        processor.run();
+        assert_eq!(processor.trace(), None);
+    }
+
+    #[test]
+    fn test_profile() {
+        let mut processor = example_processor(TraceMode::Profile);
+        let outcome = processor.run();
+        let trace = processor.trace().unwrap();
+        assert!(trace.entries().is_empty());
+
+        let profile = Profile::new(processor.program(), trace, &outcome);
+        assert_eq!(profile.hits, vec![1, 1, 1, 1, 1, 0, 1, 1, 0]);
+        assert_eq!(
+            profile.hottest_blocks[0],
+            BlockProfile {
+                block: 1..3,
+                entries: 1,
+                executed: 2
+            }
+        );
+        assert_eq!(profile.hottest_blocks.len(), 4);
+
+        let mut summary = Vec::new();
+        profile.write_summary(&mut summary, 2).unwrap();
+        let summary = String::from_utf8(summary).unwrap();
+        assert!(summary.starts_with("7 instructions executed, 7 of 9 instructions visited\n"));
+        assert!(summary.ends_with("Loop body: 1 -> 2 -> 6 -> 7 -> 3 -> 4\n"));
+    }
+}
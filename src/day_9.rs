@@ -1,4 +1,10 @@
+use std::collections::{HashMap, VecDeque};
 use std::io::BufRead;
+use std::ops::RangeInclusive;
+
+mod generator;
+
+pub use generator::{generate, GeneratedStream, GeneratorOptions, PlantedTarget};
 
 pub fn read_input<R: BufRead>(reader: &mut R) -> Vec<u64> {
     let mut numbers: Vec<u64> = Vec::new();
@@ -20,73 +26,287 @@ pub fn read_input<R: BufRead>(reader: &mut R) -> Vec<u64> {
     }
 }
 
-/// Validates the provided numbers using XMAS cipher and attempts to find the invalid number in
-/// the sequence.
-pub fn find_invalid_number(preamble_len: u32, numbers: &[u64]) -> Option<u64> {
-    let mut preamble: Vec<u64> = Vec::new();
-    for (idx, n) in numbers.iter().enumerate() {
-        if (idx as u32) < preamble_len {
-            preamble.push(*n);
-            continue;
+/// Returns an iterator over the numbers in `reader`, one per line, which stops at the first empty
+/// line.
+pub fn read_numbers<R: BufRead>(reader: R) -> impl Iterator<Item = u64> {
+    reader
+        .lines()
+        .map(|line| line.expect("Expected to read input from the reader"))
+        .take_while(|line| !line.trim().is_empty())
+        .map(|line| {
+            let line = line.trim();
+            line.parse::<u64>()
+                .unwrap_or_else(|err| panic!("Could not parse {} into an u64: {}", line, err))
+        })
+}
+
+/// A number that is not the sum of two of the numbers that precede it.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct InvalidNumber {
+    // The position of the number in the stream, starting at 0.
+    pub index: usize,
+    pub value: u64,
+}
+
+/// Validates a stream of numbers using the XMAS cipher: after the preamble, every number must be
+/// the sum of two numbers at different positions among the `preamble_len` numbers before it.
+///
+/// The window of preceding numbers is kept in a ring buffer, together with a multiset of the
+/// sums of all pairs in the window. Checking a number is a single lookup, and sliding the window
+/// updates `preamble_len - 1` sums for the number that leaves it and for the one that enters it.
+pub struct XmasValidator {
+    preamble_len: usize,
+    window: VecDeque<u64>,
+    // The number of pairs in the window that sum to every value.
+    sums: HashMap<u64, usize>,
+    // The number of numbers that were validated so far.
+    index: usize,
+}
+
+impl XmasValidator {
+    pub fn new(preamble_len: usize) -> Self {
+        XmasValidator {
+            preamble_len,
+            window: VecDeque::with_capacity(preamble_len + 1),
+            sums: HashMap::new(),
+            index: 0,
         }
+    }
+
+    /// Validates the next number in the stream, and returns it if it is invalid. Numbers in the
+    /// preamble are always valid.
+    pub fn push(&mut self, value: u64) -> Option<InvalidNumber> {
+        let index = self.index;
+        self.index += 1;
+        let invalid = self.window.len() == self.preamble_len && !self.sums.contains_key(&value);
 
-        // Check if the entry is valid, entries are only valid if
-        // any two entries in `preamble` sum to `n`, with the limitation that
-        // the two entries may not be the same entry.
-        let mut valid = false;
-        for i in preamble.iter() {
-            for j in preamble.iter() {
-                if i + j == *n {
-                    valid = true;
-                    break;
+        if self.window.len() == self.preamble_len {
+            match self.window.pop_front() {
+                Some(oldest) => {
+                    for &other in self.window.iter() {
+                        remove_sum(&mut self.sums, oldest, other);
+                    }
                 }
+                // The preamble is empty, so no number can be valid.
+                None => return Some(InvalidNumber { index, value }),
             }
-            if valid {
-                break;
+        }
+        for &other in self.window.iter() {
+            if let Some(sum) = pair_sum(value, other) {
+                *self.sums.entry(sum).or_insert(0) += 1;
             }
         }
+        self.window.push_back(value);
 
-        if !valid {
-            return Some(*n);
+        if invalid {
+            Some(InvalidNumber { index, value })
+        } else {
+            None
         }
+    }
 
-        // Remove the first entry from preamble.
-        preamble.remove(0);
-        // And push this number.
-        preamble.push(*n);
+    /// Validates every number in `numbers` and returns the invalid ones.
+    pub fn invalid_numbers<I: IntoIterator<Item = u64>>(
+        mut self,
+        numbers: I,
+    ) -> impl Iterator<Item = InvalidNumber> {
+        numbers.into_iter().filter_map(move |n| self.push(n))
     }
-    None
 }
 
-/// Finds a sequence of numbers that sums to the provided `invalid_number`
-/// by applying a windowed search.
-pub fn find_sequence(invalid_number: u64, numbers: &[u64]) -> Option<u64> {
-    for start_index in 0..(numbers.len() - 2) {
-        for end_index in (start_index + 1)..numbers.len() {
-            // Sum all entries from 'numbers' between 'start_index' and 'end_index'.
-            // If the sum is larger than 'invalid_number'.
-            let slice = &numbers[start_index..end_index];
-            let sum: u64 = slice.iter().sum();
-
-            // Found the start, and end of the sequence.
-            // Now, we need to find the smallest and largest number between
-            // numbers[start_index]..numbers[end_index] and sum them.
-            if sum == invalid_number {
-                let min_value = slice.iter().min().unwrap();
-                let max_value = slice.iter().max().unwrap();
-                return Some(min_value + max_value);
+/// Removes the sum of `a` and `b` from the multiset of pair sums.
+fn remove_sum(sums: &mut HashMap<u64, usize>, a: u64, b: u64) {
+    if let Some(sum) = pair_sum(a, b) {
+        if let Some(count) = sums.get_mut(&sum) {
+            *count -= 1;
+            if *count == 0 {
+                sums.remove(&sum);
             }
+        }
+    }
+}
 
-            if sum > invalid_number {
-                // No point in searching further using this start_index as
-                // we're already summing over the invalid number.
-                break;
-            }
+/// Returns the sum of a pair, or `None` if it does not fit in an u64 (in which case no number can
+/// be equal to it).
+fn pair_sum(a: u64, b: u64) -> Option<u64> {
+    a.checked_add(b)
+}
+
+/// Validates the provided numbers using XMAS cipher and attempts to find the invalid number in
+/// the sequence.
+pub fn find_invalid_number(preamble_len: u32, numbers: &[u64]) -> Option<u64> {
+    XmasValidator::new(preamble_len as usize)
+        .invalid_numbers(numbers.iter().copied())
+        .next()
+        .map(|invalid| invalid.value)
+}
+
+/// How well a preamble length fits the data.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PreambleCandidate {
+    pub len: usize,
+    // The number of numbers that would be flagged invalid.
+    pub invalid: usize,
+    // The number of numbers after the preamble, which are validated.
+    pub checked: usize,
+    // How plausible this length is, between 0 and 1.
+    pub confidence: f64,
+}
+
+impl PreambleCandidate {
+    pub fn invalid_rate(&self) -> f64 {
+        if self.checked == 0 {
+            return 0.0;
+        }
+        self.invalid as f64 / self.checked as f64
+    }
+}
+
+/// The result of analyzing which preamble length fits the data.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PreambleAnalysis {
+    // The analyzed lengths, shortest first.
+    pub candidates: Vec<PreambleCandidate>,
+}
+
+impl PreambleAnalysis {
+    /// Returns the most plausible preamble length, preferring the shortest on ties.
+    pub fn best(&self) -> Option<&PreambleCandidate> {
+        self.candidates
+            .iter()
+            .fold(None, |best, candidate| match best {
+                Some(best) if best.confidence >= candidate.confidence => Some(best),
+                _ => Some(candidate),
+            })
+    }
+}
+
+/// Infers the preamble length of XMAS data by validating it with every length in `lengths`.
+///
+/// Every window of a longer preamble contains the window of a shorter one, so the rate of invalid
+/// numbers drops as the length grows: shorter preambles than the real one flag a large part of
+/// the data, while the real one and longer ones only flag the few invalid numbers that were
+/// planted. The real length is therefore the one with the largest drop in invalid rate that is
+/// not followed by a similar drop. A length scores the drop of its rate compared to the length
+/// before it, minus the largest drop at a longer length, and its confidence is its share of the
+/// scores of all lengths. As every number is invalid without a preamble, the rate before a length
+/// of 1 is 1.
+///
+/// Lengths that leave no numbers to validate are skipped.
+pub fn analyze_preamble(numbers: &[u64], lengths: RangeInclusive<usize>) -> PreambleAnalysis {
+    let invalid = |len: usize| {
+        XmasValidator::new(len)
+            .invalid_numbers(numbers.iter().copied())
+            .count()
+    };
+    let start = (*lengths.start()).max(1);
+    let end = (*lengths.end()).min(numbers.len().saturating_sub(1));
+
+    let mut candidates = Vec::new();
+    let mut drops = Vec::new();
+    let mut previous_rate = match start - 1 {
+        0 => 1.0,
+        len if len < numbers.len() => invalid(len) as f64 / (numbers.len() - len) as f64,
+        _ => 0.0,
+    };
+    for len in start..=end {
+        let candidate = PreambleCandidate {
+            len,
+            invalid: invalid(len),
+            checked: numbers.len() - len,
+            confidence: 0.0,
+        };
+        drops.push((previous_rate - candidate.invalid_rate()).max(0.0));
+        previous_rate = candidate.invalid_rate();
+        candidates.push(candidate);
+    }
+
+    let mut scores = vec![0.0; drops.len()];
+    let mut largest_later_drop: f64 = 0.0;
+    for i in (0..drops.len()).rev() {
+        scores[i] = (drops[i] - largest_later_drop).max(0.0);
+        largest_later_drop = largest_later_drop.max(drops[i]);
+    }
+    let total: f64 = scores.iter().sum();
+    if total > 0.0 {
+        for (candidate, score) in candidates.iter_mut().zip(scores) {
+            candidate.confidence = score / total;
+        }
+    }
+    PreambleAnalysis { candidates }
+}
+
+/// A range of contiguous numbers.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ContiguousRange {
+    // The index of the first number in the range.
+    pub start: usize,
+    // The index directly after the last number in the range.
+    pub end: usize,
+    pub min: u64,
+    pub max: u64,
+}
+
+impl ContiguousRange {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the sum of the smallest and largest number in the range, or `None` if it does not
+    /// fit in an u64. This can only happen for a range of a single number, as the sum of the range
+    /// fits in an u64.
+    pub fn weakness(&self) -> Option<u64> {
+        self.min.checked_add(self.max)
+    }
+}
+
+/// Finds the first range of at least `min_len` contiguous numbers that sums to `target`.
+///
+/// As all numbers are non-negative, the sum of a window can only grow when it is extended and
+/// only shrink when its start moves forward, so a single pass with two pointers suffices. Only
+/// the numbers in the current window are kept in memory, so `numbers` may be a stream.
+///
+/// With a `min_len` of 1 the target itself is a valid range if it appears in the numbers, so
+/// `min_len` should be at least 2 to find a weakness.
+pub fn find_contiguous_range<I: IntoIterator<Item = u64>>(
+    target: u64,
+    numbers: I,
+    min_len: usize,
+) -> Option<ContiguousRange> {
+    let mut window: VecDeque<u64> = VecDeque::new();
+    let mut start = 0;
+    // The sum of the window, which can not overflow as it never exceeds target + u64::MAX.
+    let mut sum: u128 = 0;
+    for n in numbers {
+        window.push_back(n);
+        sum += n as u128;
+        // Keep the longest window ending at this number that doesn't exceed the target.
+        while sum > target as u128 {
+            sum -= window.pop_front().unwrap() as u128;
+            start += 1;
+        }
+        if sum == target as u128 && !window.is_empty() && window.len() >= min_len {
+            return Some(ContiguousRange {
+                start,
+                end: start + window.len(),
+                min: *window.iter().min().unwrap(),
+                max: *window.iter().max().unwrap(),
+            });
         }
     }
     None
 }
 
+/// Finds a sequence of at least two numbers that sums to the provided `invalid_number`, and
+/// returns the sum of the smallest and largest number in it.
+pub fn find_sequence(invalid_number: u64, numbers: &[u64]) -> Option<u64> {
+    find_contiguous_range(invalid_number, numbers.iter().copied(), 2).and_then(|r| r.weakness())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,8 +334,8 @@ mod tests {
 309
 576";
         let mut reader = BufReader::new(input.as_bytes());
-        let numbers = read_input(&mut reader);
-        numbers
+
+        read_input(&mut reader)
     }
 
     #[test]
@@ -131,10 +351,147 @@ mod tests {
         assert_eq!(find_invalid_number(5, &numbers).unwrap(), 127);
     }
 
+    #[test]
+    fn test_xmas_validator() {
+        let numbers = get_test_input();
+        let invalid: Vec<InvalidNumber> = XmasValidator::new(5)
+            .invalid_numbers(numbers.iter().copied())
+            .collect();
+        assert_eq!(
+            invalid,
+            vec![InvalidNumber {
+                index: 14,
+                value: 127
+            }]
+        );
+
+        // 4 is not valid, as 2 may not be paired with itself.
+        let mut validator = XmasValidator::new(3);
+        for &n in &[1, 2, 5] {
+            assert_eq!(validator.push(n), None);
+        }
+        assert_eq!(
+            validator.push(4),
+            Some(InvalidNumber { index: 3, value: 4 })
+        );
+        assert_eq!(validator.push(7), None);
+        // Every invalid number is reported, not just the first.
+        assert_eq!(
+            validator.push(14),
+            Some(InvalidNumber {
+                index: 5,
+                value: 14
+            })
+        );
+        assert_eq!(validator.push(11), None);
+
+        // Two numbers with the same value at different positions can be paired.
+        let mut validator = XmasValidator::new(3);
+        for &n in &[2, 2, 5] {
+            assert_eq!(validator.push(n), None);
+        }
+        assert_eq!(validator.push(4), None);
+
+        // Every number is invalid without a preamble.
+        let mut validator = XmasValidator::new(0);
+        assert_eq!(
+            validator.push(1),
+            Some(InvalidNumber { index: 0, value: 1 })
+        );
+    }
+
+    #[test]
+    fn test_analyze_preamble() {
+        let numbers = get_test_input();
+        let analysis = analyze_preamble(&numbers, 1..=8);
+        let invalid: Vec<(usize, usize)> = analysis
+            .candidates
+            .iter()
+            .map(|c| (c.len, c.invalid))
+            .collect();
+        assert_eq!(
+            invalid,
+            vec![
+                (1, 19),
+                (2, 18),
+                (3, 15),
+                (4, 12),
+                (5, 1),
+                (6, 1),
+                (7, 1),
+                (8, 0)
+            ]
+        );
+        let best = analysis.best().unwrap();
+        assert_eq!(best.len, 5);
+        assert!(best.confidence > 0.8);
+        assert_eq!(analysis.candidates[5].confidence, 0.0);
+
+        // Lengths that leave nothing to validate are skipped.
+        assert_eq!(analyze_preamble(&numbers, 18..=30).candidates.len(), 2);
+        assert_eq!(analyze_preamble(&[], 1..=5).best(), None);
+    }
+
+    #[test]
+    fn test_read_numbers() {
+        let numbers: Vec<u64> = read_numbers("1\n2\n3\n\n4\n".as_bytes()).collect();
+        assert_eq!(numbers, vec![1, 2, 3]);
+    }
+
     #[test]
     fn find_invalid_sequence() {
         let numbers = get_test_input();
         let invalid_number = find_invalid_number(5, &numbers).unwrap();
         assert_eq!(find_sequence(invalid_number, &numbers).unwrap(), 62);
     }
+
+    #[test]
+    fn test_find_contiguous_range() {
+        let numbers = get_test_input();
+        assert_eq!(
+            find_contiguous_range(127, numbers.iter().copied(), 2),
+            Some(ContiguousRange {
+                start: 2,
+                end: 6,
+                min: 15,
+                max: 47
+            })
+        );
+        // The target itself appears in the data as a range of length 1, but only after the
+        // range that sums to it, so the first range is still found.
+        assert_eq!(
+            find_contiguous_range(127, numbers.iter().copied(), 1),
+            Some(ContiguousRange {
+                start: 2,
+                end: 6,
+                min: 15,
+                max: 47
+            })
+        );
+        assert_eq!(
+            find_contiguous_range(150, numbers.iter().copied(), 1).map(|r| r.len()),
+            Some(1)
+        );
+        assert_eq!(find_contiguous_range(127, numbers.iter().copied(), 5), None);
+
+        // Zeros do not change the sum, but do make a range longer.
+        let numbers = [5, 0, 0, 3, 4];
+        assert_eq!(
+            find_contiguous_range(5, numbers.iter().copied(), 3),
+            Some(ContiguousRange {
+                start: 0,
+                end: 3,
+                min: 0,
+                max: 5
+            })
+        );
+
+        // Short input and large numbers.
+        assert_eq!(find_sequence(1, &[]), None);
+        assert_eq!(find_sequence(1, &[1]), None);
+        assert_eq!(find_sequence(u64::MAX, &[u64::MAX, u64::MAX, 1]), None);
+        assert_eq!(find_sequence(u64::MAX, &[1, u64::MAX - 1]), Some(u64::MAX));
+        let single = find_contiguous_range(u64::MAX, [u64::MAX].iter().copied(), 1).unwrap();
+        assert_eq!(single.weakness(), None);
+    }
 }
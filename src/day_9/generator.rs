@@ -0,0 +1,389 @@
+use super::{InvalidNumber, XmasValidator};
+use crate::random::XorShift;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::ops::Range;
+
+/// The largest number of numbers `generate` produces, as they are all kept in memory.
+const MAX_LEN: usize = 1_000_000;
+
+/// A number that is planted as the sum of a range of contiguous numbers.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlantedTarget {
+    // The numbers that sum to the target, which must precede it.
+    pub range: Range<usize>,
+    // The position at which the target is emitted.
+    pub position: usize,
+}
+
+/// Configures the XMAS stream `generate` produces.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GeneratorOptions {
+    pub seed: u64,
+    pub preamble_len: usize,
+    // The total number of numbers, including the preamble.
+    pub len: usize,
+    // The range of the numbers in the preamble. Later numbers grow beyond it, as every valid
+    // number is the sum of two preceding numbers.
+    pub min_value: u64,
+    pub max_value: u64,
+    // The positions at which a number that is not the sum of two preceding numbers is emitted.
+    pub invalid_at: Vec<usize>,
+    pub targets: Vec<PlantedTarget>,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            seed: 0,
+            preamble_len: 25,
+            len: 1000,
+            min_value: 1,
+            max_value: 50,
+            invalid_at: Vec::new(),
+            targets: Vec::new(),
+        }
+    }
+}
+
+/// A generated XMAS stream, and the ground truth about it.
+#[derive(Debug, PartialEq, Clone)]
+pub struct GeneratedStream {
+    pub numbers: Vec<u64>,
+    // Every invalid number in the stream: the injected ones, and planted targets that happen to
+    // be invalid.
+    pub invalid: Vec<InvalidNumber>,
+    // The planted targets, with their values.
+    pub targets: Vec<(PlantedTarget, u64)>,
+}
+
+impl GeneratedStream {
+    /// Writes the numbers, one per line, in the format that `read_input` reads.
+    pub fn write_numbers<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for n in &self.numbers {
+            writeln!(writer, "{}", n)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the ground truth as one fact per line.
+    pub fn write_truth<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for invalid in &self.invalid {
+            writeln!(writer, "invalid {} {}", invalid.index, invalid.value)?;
+        }
+        for (target, value) in &self.targets {
+            writeln!(
+                writer,
+                "target {} {}..{} {}",
+                target.position, target.range.start, target.range.end, value
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Generates an XMAS stream in which every number after the preamble is the sum of two numbers
+/// at different positions among the `preamble_len` numbers before it, except at the positions
+/// where invalid numbers or targets are planted.
+///
+/// The same options always generate the same stream. As every valid number is at least the sum
+/// of the two smallest numbers in its window, the numbers at least double every `preamble_len`
+/// numbers, so long streams need a long preamble to fit in an u64.
+pub fn generate(options: &GeneratorOptions) -> Result<GeneratedStream, String> {
+    validate(options)?;
+    let invalid_at: HashSet<usize> = options.invalid_at.iter().copied().collect();
+    let mut random = XorShift::new(options.seed);
+    let mut numbers: Vec<u64> = Vec::with_capacity(options.len);
+    let mut preamble: HashSet<u64> = HashSet::new();
+    let mut targets: Vec<(PlantedTarget, u64)> = Vec::new();
+
+    for position in 0..options.len {
+        if position < options.preamble_len {
+            // The values in the preamble are distinct, to give the numbers after it some variety.
+            let value = loop {
+                let value = random.between(options.min_value, options.max_value);
+                if preamble.insert(value) {
+                    break value;
+                }
+            };
+            numbers.push(value);
+            continue;
+        }
+
+        let window = &numbers[position - options.preamble_len..position];
+        let overflow = || format!("The number at position {} does not fit in an u64", position);
+        let value = if invalid_at.contains(&position) {
+            invalid_number(window, &mut random)
+                .map_err(|e| format!("The number at position {} {}", position, e))?
+        } else if let Some(target) = options.targets.iter().find(|t| t.position == position) {
+            let value = numbers[target.range.clone()]
+                .iter()
+                .try_fold(0u64, |sum, &n| sum.checked_add(n))
+                .ok_or_else(overflow)?;
+            targets.push((target.clone(), value));
+            value
+        } else {
+            valid_number(window, &mut random)
+                .map_err(|e| format!("The number at position {} {}", position, e))?
+        };
+        numbers.push(value);
+    }
+
+    let invalid = XmasValidator::new(options.preamble_len)
+        .invalid_numbers(numbers.iter().copied())
+        .collect();
+    Ok(GeneratedStream {
+        numbers,
+        invalid,
+        targets,
+    })
+}
+
+fn validate(options: &GeneratorOptions) -> Result<(), String> {
+    if options.len > MAX_LEN {
+        return Err(format!(
+            "The stream can contain at most {} numbers, but {} were requested",
+            MAX_LEN, options.len
+        ));
+    }
+    if options.preamble_len < 2 {
+        return Err("The preamble must contain at least two numbers".to_string());
+    }
+    if options.min_value > options.max_value
+        || options.max_value - options.min_value < options.preamble_len as u64 - 1
+    {
+        return Err(format!(
+            "The range {}..={} does not contain {} distinct values",
+            options.min_value, options.max_value, options.preamble_len
+        ));
+    }
+    let mut planted: HashSet<usize> = HashSet::new();
+    let positions = options
+        .invalid_at
+        .iter()
+        .chain(options.targets.iter().map(|t| &t.position));
+    for &position in positions {
+        if position < options.preamble_len || position >= options.len {
+            return Err(format!(
+                "Position {} is not between the preamble and the end of the stream",
+                position
+            ));
+        }
+        if !planted.insert(position) {
+            return Err(format!("Position {} is planted more than once", position));
+        }
+    }
+    for target in options.targets.iter() {
+        if target.range.start >= target.range.end || target.range.end > target.position {
+            return Err(format!(
+                "The range {}..{} must be non-empty and precede its target at {}",
+                target.range.start, target.range.end, target.position
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns the sum of two numbers at different positions in the window. The numbers are picked
+/// among the smaller half of the window, to keep the stream from growing faster than needed.
+fn valid_number(window: &[u64], random: &mut XorShift) -> Result<u64, String> {
+    if window.len() < 2 {
+        return Err("has less than two numbers to pair".to_string());
+    }
+    let mut values = window.to_vec();
+    values.sort_unstable();
+    let candidates = (values.len() / 2).max(2);
+    let a = random.between(0, candidates as u64 - 1) as usize;
+    let b = loop {
+        let b = random.between(0, candidates as u64 - 1) as usize;
+        if b != a {
+            break b;
+        }
+    };
+    values[a]
+        .checked_add(values[b])
+        .ok_or_else(|| "does not fit in an u64".to_string())
+}
+
+/// Returns a number within the range of the window that is neither in the window nor the sum of
+/// two numbers in it, or the number directly after the largest number and sum if there is no
+/// such number.
+fn invalid_number(window: &[u64], random: &mut XorShift) -> Result<u64, String> {
+    let mut sums: HashSet<u64> = HashSet::new();
+    for (i, &a) in window.iter().enumerate() {
+        for &b in &window[i + 1..] {
+            if let Some(sum) = a.checked_add(b) {
+                sums.insert(sum);
+            }
+        }
+    }
+    let min = *window.iter().min().unwrap();
+    let max = *window.iter().max().unwrap();
+    for _ in 0..100 {
+        let value = random.between(min, max);
+        if !sums.contains(&value) && !window.contains(&value) {
+            return Ok(value);
+        }
+    }
+    sums.iter()
+        .chain(window.iter())
+        .max()
+        .and_then(|max| max.checked_add(1))
+        .ok_or_else(|| "has no invalid value that fits in an u64".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day_9::{analyze_preamble, find_contiguous_range, find_invalid_number};
+
+    #[test]
+    fn test_generate() {
+        let options = GeneratorOptions {
+            seed: 42,
+            preamble_len: 25,
+            len: 1000,
+            invalid_at: vec![100, 700],
+            ..GeneratorOptions::default()
+        };
+        let stream = generate(&options).unwrap();
+        assert_eq!(stream.numbers.len(), 1000);
+        assert_eq!(
+            stream.invalid.iter().map(|i| i.index).collect::<Vec<_>>(),
+            vec![100, 700]
+        );
+        assert_eq!(
+            find_invalid_number(25, &stream.numbers),
+            Some(stream.numbers[100])
+        );
+
+        // The same seed generates the same stream.
+        assert_eq!(generate(&options), Ok(stream.clone()));
+        let other = GeneratorOptions {
+            seed: 43,
+            ..options
+        };
+        assert_ne!(generate(&other).unwrap().numbers, stream.numbers);
+
+        assert_eq!(
+            analyze_preamble(&stream.numbers, 2..=40)
+                .best()
+                .unwrap()
+                .len,
+            25
+        );
+    }
+
+    #[test]
+    fn test_generate_targets() {
+        let options = GeneratorOptions {
+            seed: 7,
+            preamble_len: 10,
+            len: 300,
+            targets: vec![PlantedTarget {
+                range: 120..134,
+                position: 200,
+            }],
+            ..GeneratorOptions::default()
+        };
+        let stream = generate(&options).unwrap();
+        let (target, value) = &stream.targets[0];
+        assert_eq!(stream.numbers[200], *value);
+        assert_eq!(*value, stream.numbers[120..134].iter().sum::<u64>());
+        // The sum of many numbers is larger than the sum of any two numbers before it.
+        assert_eq!(stream.invalid[0].index, target.position);
+
+        let range = find_contiguous_range(*value, stream.numbers.iter().copied(), 2).unwrap();
+        assert_eq!(
+            stream.numbers[range.start..range.end].iter().sum::<u64>(),
+            *value
+        );
+
+        let mut truth = Vec::new();
+        stream.write_truth(&mut truth).unwrap();
+        assert_eq!(
+            String::from_utf8(truth).unwrap(),
+            format!("invalid 200 {}\ntarget 200 120..134 {}\n", value, value)
+        );
+    }
+
+    #[test]
+    fn test_generate_short_preamble() {
+        // An invalid number right after a preamble of two numbers used to repeat a value of the
+        // window, leaving a single distinct value to pair.
+        let options = GeneratorOptions {
+            seed: 2,
+            preamble_len: 2,
+            len: 10,
+            min_value: 1,
+            max_value: 3,
+            invalid_at: vec![2],
+            ..GeneratorOptions::default()
+        };
+        let stream = generate(&options).unwrap();
+        assert_eq!(stream.numbers.len(), 10);
+        assert!(!stream.numbers[..2].contains(&stream.numbers[2]));
+        assert_eq!(
+            stream.invalid.iter().map(|i| i.index).collect::<Vec<_>>(),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn test_invalid_number() {
+        let mut random = XorShift::new(1);
+        let window = [1, 2, 3];
+        for _ in 0..10 {
+            let value = invalid_number(&window, &mut random).unwrap();
+            assert!(![1, 2, 3, 4, 5].contains(&value));
+        }
+        // Every value in the range of the window is taken, so the next number is planted.
+        assert_eq!(invalid_number(&[1, 2], &mut random), Ok(4));
+        // There is no room above the window for an invalid number.
+        assert!(invalid_number(&[u64::MAX - 1, u64::MAX], &mut random).is_err());
+    }
+
+    #[test]
+    fn test_generate_errors() {
+        let generate_with = |options: GeneratorOptions| generate(&options).err();
+        assert!(generate_with(GeneratorOptions {
+            invalid_at: vec![3],
+            ..GeneratorOptions::default()
+        })
+        .is_some());
+        assert!(generate_with(GeneratorOptions {
+            max_value: 10,
+            ..GeneratorOptions::default()
+        })
+        .is_some());
+        assert!(generate_with(GeneratorOptions {
+            targets: vec![PlantedTarget {
+                range: 50..60,
+                position: 55
+            }],
+            ..GeneratorOptions::default()
+        })
+        .is_some());
+        assert_eq!(
+            generate_with(GeneratorOptions {
+                len: usize::MAX,
+                ..GeneratorOptions::default()
+            }),
+            Some(format!(
+                "The stream can contain at most {} numbers, but {} were requested",
+                MAX_LEN,
+                usize::MAX
+            ))
+        );
+        // The numbers double at least every 5 numbers, so they overflow long before the end.
+        assert_eq!(
+            generate_with(GeneratorOptions {
+                preamble_len: 5,
+                len: 1000,
+                ..GeneratorOptions::default()
+            })
+            .map(|e| e.ends_with("does not fit in an u64")),
+            Some(true)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate lazy_static;
 
+pub mod cli;
 pub mod day_1;
 pub mod day_10;
 pub mod day_11;
@@ -14,3 +15,5 @@ pub mod day_6;
 pub mod day_7;
 pub mod day_8;
 pub mod day_9;
+
+mod random;